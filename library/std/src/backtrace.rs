@@ -155,6 +155,9 @@ pub struct BacktraceFrame {
 #[derive(Debug)]
 enum RawFrame {
     Actual(backtrace_rs::Frame),
+    /// A frame found by the platform's own unwinder, which has no symbols.
+    #[cfg(target_os = "xous")]
+    Address(usize),
     #[cfg(test)]
     Fake,
 }
@@ -339,6 +342,15 @@ fn create(ip: usize) -> Backtrace {
                 true
             });
         }
+        // `backtrace` can't walk the stack on Xous, so the loop above finds nothing.
+        #[cfg(target_os = "xous")]
+        crate::sys::backtrace::trace(|frame| {
+            frames.push(BacktraceFrame { frame: RawFrame::Address(frame.ip), symbols: Vec::new() });
+            if frame.symbol_address == ip && actual_start.is_none() {
+                actual_start = Some(frames.len());
+            }
+            true
+        });
 
         // If no frames came out assume that this is an unsupported platform
         // since `backtrace` doesn't provide a way of learning this right now,
@@ -440,6 +452,8 @@ fn lazy_resolve(mut capture: Capture) -> LazyResolve {
             let symbols = &mut frame.symbols;
             let frame = match &frame.frame {
                 RawFrame::Actual(frame) => frame,
+                #[cfg(target_os = "xous")]
+                RawFrame::Address(_) => continue,
                 #[cfg(test)]
                 RawFrame::Fake => unimplemented!(),
             };
@@ -466,6 +480,8 @@ impl RawFrame {
     fn ip(&self) -> *mut c_void {
         match self {
             RawFrame::Actual(frame) => frame.ip(),
+            #[cfg(target_os = "xous")]
+            RawFrame::Address(ip) => crate::ptr::without_provenance_mut(*ip),
             #[cfg(test)]
             RawFrame::Fake => crate::ptr::without_provenance_mut(1),
         }
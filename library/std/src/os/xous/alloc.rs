@@ -0,0 +1,95 @@
+//! Xous-specific heap statistics and limits.
+//!
+//! These describe the [`System`](crate::alloc::System) allocator, which is the
+//! global allocator unless a program picks a different one.
+
+#![stable(feature = "rust1", since = "1.0.0")]
+
+use crate::fmt;
+use crate::io;
+use crate::sys::alloc as alloc_imp;
+
+/// A snapshot of heap usage, returned by [`heap_stats`].
+#[derive(Copy, Clone)]
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct HeapStats {
+    in_use: usize,
+    peak: usize,
+    footprint: usize,
+}
+
+impl HeapStats {
+    /// Returns the number of bytes that are currently allocated.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn bytes_in_use(&self) -> usize {
+        self.in_use
+    }
+
+    /// Returns the most bytes that have been allocated at the same time since
+    /// the process started.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn peak_bytes_in_use(&self) -> usize {
+        self.peak
+    }
+
+    /// Returns the amount of memory that the heap occupies, in bytes. This is
+    /// larger than [`bytes_in_use`](HeapStats::bytes_in_use) because it also
+    /// counts free space and allocator overhead.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn footprint(&self) -> usize {
+        self.footprint
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl fmt::Debug for HeapStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeapStats")
+            .field("bytes_in_use", &self.in_use)
+            .field("peak_bytes_in_use", &self.peak)
+            .field("footprint", &self.footprint)
+            .finish()
+    }
+}
+
+/// Returns the current heap usage of this process.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::alloc::heap_stats;
+///
+/// let stats = heap_stats().unwrap();
+/// println!("{} bytes in use, {} at most", stats.bytes_in_use(), stats.peak_bytes_in_use());
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn heap_stats() -> io::Result<HeapStats> {
+    let (in_use, peak) = alloc_imp::heap_usage();
+    let footprint = alloc_imp::heap_footprint()?;
+    Ok(HeapStats { in_use, peak, footprint })
+}
+
+/// Returns the most memory, in bytes, that the heap of this process may grow
+/// to occupy.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn heap_limit() -> io::Result<usize> {
+    alloc_imp::heap_limit()
+}
+
+/// Limits the heap of this process to at most `limit` bytes. Once the heap
+/// reaches this size, allocations that need more memory fail.
+///
+/// The limit may not be set below the current footprint of the heap, nor
+/// above what the system allows for this process.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::alloc::set_heap_limit;
+///
+/// set_heap_limit(512 * 1024).unwrap();
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn set_heap_limit(limit: usize) -> io::Result<()> {
+    alloc_imp::set_heap_limit(limit)
+}
@@ -567,6 +567,50 @@ pub(crate) fn join_thread(thread_id: ThreadId) -> Result<usize, Error> {
     }
 }
 
+/// Register `handler` to be called whenever a thread in this process takes an
+/// exception that the kernel can't resolve, such as an access to an unmapped page.
+/// The handler runs on `stack` rather than on the faulting thread's stack, and is
+/// passed the RISC-V `mcause` code, the faulting program counter, and the faulting
+/// address in that order. The kernel takes the handler's program counter and
+/// initial stack pointer, and stacks grow down, so it's given the top of `stack`.
+pub(crate) fn set_exception_handler(
+    handler: extern "C" fn(usize, usize, usize) -> !,
+    stack: *mut [u8],
+) -> Result<(), Error> {
+    let mut a0 = Syscall::SetExceptionHandler as usize;
+    let mut a1 = handler as usize;
+    let a2 = stack.as_mut_ptr() as usize + stack.len();
+    let a3 = 0;
+    let a4 = 0;
+    let a5 = 0;
+    let a6 = 0;
+    let a7 = 0;
+
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("a0") a0,
+            inlateout("a1") a1,
+            inlateout("a2") a2 => _,
+            inlateout("a3") a3 => _,
+            inlateout("a4") a4 => _,
+            inlateout("a5") a5 => _,
+            inlateout("a6") a6 => _,
+            inlateout("a7") a7 => _,
+        )
+    };
+
+    let result = a0;
+
+    if result == SyscallResult::Ok as usize {
+        Ok(())
+    } else if result == SyscallResult::Error as usize {
+        Err(a1.into())
+    } else {
+        Err(Error::InternalError)
+    }
+}
+
 /// Get the current thread's ID
 pub(crate) fn thread_id() -> Result<ThreadId, Error> {
     let mut a0 = Syscall::GetThreadId as usize;
@@ -603,6 +647,42 @@ pub(crate) fn thread_id() -> Result<ThreadId, Error> {
     }
 }
 
+/// Get the current process's ID
+pub(crate) fn process_id() -> Result<u32, Error> {
+    let mut a0 = Syscall::GetProcessId as usize;
+    let mut a1 = 0;
+    let a2 = 0;
+    let a3 = 0;
+    let a4 = 0;
+    let a5 = 0;
+    let a6 = 0;
+    let a7 = 0;
+
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("a0") a0,
+            inlateout("a1") a1,
+            inlateout("a2") a2 => _,
+            inlateout("a3") a3 => _,
+            inlateout("a4") a4 => _,
+            inlateout("a5") a5 => _,
+            inlateout("a6") a6 => _,
+            inlateout("a7") a7 => _,
+        )
+    };
+
+    let result = a0;
+
+    if result == SyscallResult::ProcessId as usize {
+        Ok(a1 as u32)
+    } else if result == SyscallResult::Error as usize {
+        Err(a1.into())
+    } else {
+        Err(Error::InternalError)
+    }
+}
+
 /// Adjust the given `knob` limit to match the new value `new`. The current value must
 /// match the `current` in order for this to take effect.
 ///
@@ -614,7 +694,7 @@ pub(crate) fn thread_id() -> Result<ThreadId, Error> {
 pub(crate) fn adjust_limit(knob: Limits, current: usize, new: usize) -> Result<usize, Error> {
     let mut a0 = Syscall::AdjustProcessLimit as usize;
     let mut a1 = knob as usize;
-    let a2 = current;
+    let mut a2 = current;
     let a3 = new;
     let a4 = 0;
     let a5 = 0;
@@ -626,7 +706,7 @@ pub(crate) fn adjust_limit(knob: Limits, current: usize, new: usize) -> Result<u
             "ecall",
             inlateout("a0") a0,
             inlateout("a1") a1,
-            inlateout("a2") a2 => _,
+            inlateout("a2") a2,
             inlateout("a3") a3 => _,
             inlateout("a4") a4 => _,
             inlateout("a5") a5 => _,
@@ -637,10 +717,171 @@ pub(crate) fn adjust_limit(knob: Limits, current: usize, new: usize) -> Result<u
 
     let result = a0;
 
-    if result == SyscallResult::Scalar2 as usize && a1 == knob as usize {
+    // The kernel echoes the knob in `a1` and puts the resulting value in `a2`,
+    // whichever kind of scalar it replies with.
+    if (result == SyscallResult::Scalar2 as usize || result == SyscallResult::Scalar5 as usize)
+        && a1 == knob as usize
+    {
         Ok(a2)
-    } else if result == SyscallResult::Scalar5 as usize && a1 == knob as usize {
-        Ok(a1)
+    } else if result == SyscallResult::Error as usize {
+        Err(a1.into())
+    } else {
+        Err(Error::InternalError)
+    }
+}
+
+/// Create a new server with the given 128-bit `address`. Messages sent to this
+/// address may then be retrieved with `receive_message()`.
+pub(crate) fn create_server_with_address(address: [u32; 4]) -> Result<[u32; 4], Error> {
+    let mut a0 = Syscall::CreateServerWithAddress as usize;
+    let mut a1 = address[0] as usize;
+    let mut a2 = address[1] as usize;
+    let mut a3 = address[2] as usize;
+    let mut a4 = address[3] as usize;
+    let a5 = 0;
+    let a6 = 0;
+    let a7 = 0;
+
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("a0") a0,
+            inlateout("a1") a1,
+            inlateout("a2") a2,
+            inlateout("a3") a3,
+            inlateout("a4") a4,
+            inlateout("a5") a5 => _,
+            inlateout("a6") a6 => _,
+            inlateout("a7") a7 => _,
+        )
+    };
+
+    let result = a0;
+
+    if result == SyscallResult::ServerId as usize {
+        Ok([a1 as u32, a2 as u32, a3 as u32, a4 as u32])
+    } else if result == SyscallResult::Error as usize {
+        Err(a1.into())
+    } else {
+        Err(Error::InternalError)
+    }
+}
+
+/// Block until a message arrives for the server with the given `address`.
+///
+/// The message envelope is returned as its raw registers: the sender, the kind
+/// of message (an `InvokeType`), the opcode, and four words whose meaning depends
+/// on the kind. For memory messages these are the address and length of the
+/// buffer followed by the two arguments; for scalar messages they are the arguments.
+pub(crate) fn receive_message(address: [u32; 4]) -> Result<[usize; 7], Error> {
+    let mut a0 = Syscall::ReceiveMessage as usize;
+    let mut a1 = address[0] as usize;
+    let mut a2 = address[1] as usize;
+    let mut a3 = address[2] as usize;
+    let mut a4 = address[3] as usize;
+    let mut a5 = 0;
+    let mut a6 = 0;
+    let mut a7 = 0;
+
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("a0") a0,
+            inlateout("a1") a1,
+            inlateout("a2") a2,
+            inlateout("a3") a3,
+            inlateout("a4") a4,
+            inlateout("a5") a5,
+            inlateout("a6") a6,
+            inlateout("a7") a7,
+        )
+    };
+
+    let result = a0;
+
+    if result == SyscallResult::Message as usize {
+        Ok([a1, a2, a3, a4, a5, a6, a7])
+    } else if result == SyscallResult::Error as usize {
+        Err(a1.into())
+    } else {
+        Err(Error::InternalError)
+    }
+}
+
+/// Return memory that was lent to this process back to the `sender`, along with
+/// two result values that show up in the sender's `offset` and `valid` fields.
+///
+/// Safety: `range` must be the buffer that was received from `sender`, and it
+/// must not be accessed after this call, even if this function returns Err().
+pub(crate) unsafe fn return_memory(
+    sender: usize,
+    range: *const [u8],
+    offset: usize,
+    valid: usize,
+) -> Result<(), Error> {
+    let mut a0 = Syscall::ReturnMemory as usize;
+    let mut a1 = sender;
+    let a2 = range as *const u8 as usize;
+    let a3 = range.len();
+    let a4 = offset;
+    let a5 = valid;
+    let a6 = 0;
+    let a7 = 0;
+
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("a0") a0,
+            inlateout("a1") a1,
+            inlateout("a2") a2 => _,
+            inlateout("a3") a3 => _,
+            inlateout("a4") a4 => _,
+            inlateout("a5") a5 => _,
+            inlateout("a6") a6 => _,
+            inlateout("a7") a7 => _,
+        )
+    };
+
+    let result = a0;
+
+    if result == SyscallResult::Ok as usize {
+        Ok(())
+    } else if result == SyscallResult::Error as usize {
+        Err(a1.into())
+    } else {
+        Err(Error::InternalError)
+    }
+}
+
+/// Respond to a blocking scalar message from `sender` with five values.
+pub(crate) fn return_scalar(sender: usize, values: [usize; 5]) -> Result<(), Error> {
+    let mut a0 = Syscall::ReturnScalar as usize;
+    let mut a1 = sender;
+    let a2 = values[0];
+    let a3 = values[1];
+    let a4 = values[2];
+    let a5 = values[3];
+    let a6 = values[4];
+    let a7 = 0;
+
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("a0") a0,
+            inlateout("a1") a1,
+            inlateout("a2") a2 => _,
+            inlateout("a3") a3 => _,
+            inlateout("a4") a4 => _,
+            inlateout("a5") a5 => _,
+            inlateout("a6") a6 => _,
+            inlateout("a7") a7 => _,
+        )
+    };
+
+    let result = a0;
+
+    if result == SyscallResult::Ok as usize {
+        Ok(())
     } else if result == SyscallResult::Error as usize {
         Err(a1.into())
     } else {
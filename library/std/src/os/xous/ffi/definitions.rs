@@ -9,6 +9,7 @@ pub enum Syscall {
     MapMemory = 2,
     Yield = 3,
     UpdateMemoryFlags = 12,
+    CreateServerWithAddress = 14,
     ReceiveMessage = 15,
     SendMessage = 16,
     Connect = 17,
@@ -19,7 +20,9 @@ pub enum Syscall {
     TrySendMessage = 24,
     TryConnect = 25,
     GetThreadId = 32,
+    GetProcessId = 33,
     JoinThread = 36,
+    SetExceptionHandler = 37,
     AdjustProcessLimit = 38,
     ReturnScalar = 40,
 }
@@ -33,9 +36,11 @@ pub enum SyscallResult {
     Ok = 0,
     Error = 1,
     MemoryRange = 3,
+    ServerId = 6,
     ConnectionId = 7,
     Message = 9,
     ThreadId = 10,
+    ProcessId = 11,
     Scalar1 = 14,
     Scalar2 = 15,
     MemoryReturned = 18,
@@ -228,6 +233,7 @@ pub enum ServerAddressError {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct ServerAddress([u32; 4]);
 
 #[stable(feature = "rust1", since = "1.0.0")]
@@ -252,6 +258,13 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
     }
 }
 
+#[stable(feature = "rust1", since = "1.0.0")]
+impl From<[u32; 4]> for ServerAddress {
+    fn from(src: [u32; 4]) -> ServerAddress {
+        ServerAddress(src)
+    }
+}
+
 #[stable(feature = "rust1", since = "1.0.0")]
 impl Into<[u32; 4]> for ServerAddress {
     fn into(self) -> [u32; 4] {
@@ -1,6 +1,7 @@
 #![stable(feature = "rust1", since = "1.0.0")]
 
 use crate::fs;
+use crate::io;
 use crate::sys_common::AsInner;
 
 #[stable(feature = "file_type_ext", since = "1.5.0")]
@@ -31,3 +32,95 @@ fn is_basis(&self) -> bool {
         self.as_inner().is_basis()
     }
 }
+
+/// Xous-specific extensions to [`fs::File`].
+#[stable(feature = "rust1", since = "1.0.0")]
+pub trait FileExt {
+    /// Returns the name of the basis that this file was found in.
+    ///
+    /// Paths that don't name a basis are searched for in every open basis,
+    /// so this is the only way to find out where such a file actually lives.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::os::xous::fs::FileExt;
+    /// use std::io;
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let file = File::open("wallet:seed")?;
+    ///     println!("seed is stored in {}", file.basis()?);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn basis(&self) -> io::Result<String>;
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl FileExt for fs::File {
+    fn basis(&self) -> io::Result<String> {
+        self.as_inner().basis()
+    }
+}
+
+/// Returns the names of all open bases, in the order that they are searched
+/// when a path doesn't name a basis. The most recently opened basis comes first.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::fs::list_bases;
+///
+/// for basis in list_bases().unwrap() {
+///     println!("{basis}");
+/// }
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn list_bases() -> io::Result<Vec<String>> {
+    crate::sys::fs::list_bases()
+}
+
+/// Creates a new basis called `name`, protected by `password`. The new basis
+/// is not opened; use [`open_basis`] to start using it.
+///
+/// Returns an error of kind [`io::ErrorKind::AlreadyExists`] if a basis with
+/// this name already exists.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn create_basis(name: &str, password: &str) -> io::Result<()> {
+    crate::sys::fs::create_basis(name, password)
+}
+
+/// Unlocks the basis called `name` with `password`, making the keys it contains
+/// visible. Paths that don't name a basis will find keys in this basis first.
+///
+/// Returns an error of kind [`io::ErrorKind::PermissionDenied`] if the password
+/// is wrong, and [`io::ErrorKind::NotFound`] if there is no such basis. Since
+/// bases are plausibly deniable, these two cases may be indistinguishable.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::fs::{close_basis, open_basis};
+/// use std::io;
+///
+/// fn main() -> io::Result<()> {
+///     open_basis("private", "correct horse battery staple")?;
+///     let seed = std::fs::read(":private:wallet:seed")?;
+///     println!("seed is {} bytes long", seed.len());
+///     close_basis("private")?;
+///     Ok(())
+/// }
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn open_basis(name: &str, password: &str) -> io::Result<()> {
+    crate::sys::fs::open_basis(name, password)
+}
+
+/// Locks the basis called `name`, hiding the keys it contains until it is
+/// opened again. Files that are open in this basis can no longer be used.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn close_basis(name: &str) -> io::Result<()> {
+    crate::sys::fs::close_basis(name)
+}
@@ -0,0 +1,352 @@
+//! Safe wrappers for passing messages between Xous processes.
+//!
+//! A server is registered with [`Server::register`], and then handles requests
+//! one at a time by calling [`Server::receive`]. Each request arrives as a
+//! [`MessageEnvelope`], which is answered with [`MessageEnvelope::return_scalar`]
+//! or [`MessageEnvelope::return_memory`]. If an envelope is dropped without an
+//! answer, the sender is released with an empty response so that it never
+//! blocks forever.
+//!
+//! Clients use [`lend`] and [`lend_mut`] to share a [`PageBuffer`] with a server,
+//! and [`scalar`] and [`blocking_scalar`] to send small requests.
+
+#![stable(feature = "rust1", since = "1.0.0")]
+
+use crate::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use crate::fmt;
+use crate::ops::{Deref, DerefMut};
+use crate::os::xous::ffi::{
+    self, create_server_with_address, receive_message, return_memory, return_scalar, unmap_memory,
+    Connection, Error, InvokeType, ServerAddress,
+};
+use crate::os::xous::services::register_name;
+use crate::ptr::NonNull;
+
+/// Memory that is lent to another process must be page-aligned and a whole
+/// number of pages long.
+pub(crate) const PAGE_SIZE: usize = 4096;
+
+/// A server that other processes may connect to and send messages to.
+#[stable(feature = "rust1", since = "1.0.0")]
+#[derive(Debug)]
+pub struct Server {
+    address: ServerAddress,
+}
+
+impl Server {
+    /// Register a new server under `name` with the name server, so that clients
+    /// can connect to it with [`crate::os::xous::services::connect`].
+    ///
+    /// Returns [`Error::ServerExists`] if another server already uses this name,
+    /// or [`Error::InvalidString`] if the name is empty or longer than 64 bytes.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn register(name: &str) -> Result<Server, Error> {
+        Server::with_address(register_name(name)?)
+    }
+
+    /// Create a new server at a fixed `address`. Clients connect to it by address
+    /// using [`connect`], bypassing the name server.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn with_address(address: ServerAddress) -> Result<Server, Error> {
+        let address = create_server_with_address(address.into())?;
+        Ok(Server { address: address.into() })
+    }
+
+    /// The address of this server.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn address(&self) -> ServerAddress {
+        self.address
+    }
+
+    /// Block until the next message for this server arrives.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn receive(&self) -> Result<MessageEnvelope, Error> {
+        let [sender, kind, opcode, a1, a2, a3, a4] = receive_message(self.address.into())?;
+        let kind = match kind {
+            x if x == InvokeType::LendMut as usize => MessageKind::LendMut,
+            x if x == InvokeType::Lend as usize => MessageKind::Lend,
+            x if x == InvokeType::Move as usize => MessageKind::Move,
+            x if x == InvokeType::Scalar as usize => MessageKind::Scalar,
+            x if x == InvokeType::BlockingScalar as usize => MessageKind::BlockingScalar,
+            _ => return Err(Error::InternalError),
+        };
+        Ok(MessageEnvelope { sender, kind, opcode, args: [a1, a2, a3, a4], answered: false })
+    }
+}
+
+/// The different kinds of messages a server may receive.
+#[stable(feature = "rust1", since = "1.0.0")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageKind {
+    /// A buffer the server may modify. The sender is blocked until it is returned.
+    LendMut,
+    /// A read-only buffer. The sender is blocked until it is returned.
+    Lend,
+    /// A buffer whose ownership has been transferred to the server.
+    Move,
+    /// Up to four words of data. The sender does not wait for a response.
+    Scalar,
+    /// Up to four words of data. The sender is blocked until the server responds.
+    BlockingScalar,
+}
+
+/// A message received by a [`Server`], along with the information required to
+/// respond to it.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct MessageEnvelope {
+    sender: usize,
+    kind: MessageKind,
+    opcode: usize,
+    args: [usize; 4],
+    answered: bool,
+}
+
+impl MessageEnvelope {
+    /// What kind of message this is.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn kind(&self) -> MessageKind {
+        self.kind
+    }
+
+    /// The opcode the sender attached to this message.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn opcode(&self) -> usize {
+        self.opcode
+    }
+
+    /// The sender of this message. The value is opaque, but is stable for the
+    /// lifetime of the sending thread's connection.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn sender(&self) -> usize {
+        self.sender
+    }
+
+    /// The four arguments of a scalar message, or `None` for memory messages.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn scalars(&self) -> Option<[usize; 4]> {
+        match self.kind {
+            MessageKind::Scalar | MessageKind::BlockingScalar => Some(self.args),
+            _ => None,
+        }
+    }
+
+    /// The two arguments that accompany a memory message, conventionally the
+    /// offset and the number of valid bytes. Returns `None` for scalar messages.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn memory_args(&self) -> Option<(usize, usize)> {
+        if self.is_memory() {
+            Some((self.args[2], self.args[3]))
+        } else {
+            None
+        }
+    }
+
+    /// The buffer that was sent with this message, or `None` for scalar messages.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn buffer(&self) -> Option<&[u8]> {
+        if !self.is_memory() {
+            return None;
+        }
+        // SAFETY: The kernel mapped this range into our address space when the
+        // message was received, and it remains mapped until it is returned or
+        // unmapped, both of which consume the envelope.
+        Some(unsafe { crate::slice::from_raw_parts(self.args[0] as *const u8, self.args[1]) })
+    }
+
+    /// The buffer that was sent with this message, if the server is allowed to
+    /// modify it. Only `LendMut` and `Move` messages may be modified.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn buffer_mut(&mut self) -> Option<&mut [u8]> {
+        if self.kind != MessageKind::LendMut && self.kind != MessageKind::Move {
+            return None;
+        }
+        // SAFETY: See `buffer()`. The sender gave up write access to this range
+        // for the duration of the lend, so we are its only user.
+        Some(unsafe { crate::slice::from_raw_parts_mut(self.args[0] as *mut u8, self.args[1]) })
+    }
+
+    /// Respond to a `BlockingScalar` message, unblocking the sender. Returns
+    /// [`Error::InvalidSyscall`] if this is any other kind of message.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn return_scalar(mut self, values: [usize; 5]) -> Result<(), Error> {
+        if self.kind != MessageKind::BlockingScalar {
+            return Err(Error::InvalidSyscall);
+        }
+        self.answered = true;
+        return_scalar(self.sender, values)
+    }
+
+    /// Hand a lent buffer back to the sender, unblocking it. `offset` and `valid`
+    /// are delivered to the sender as the result of its `lend` or `lend_mut` call.
+    /// Returns [`Error::InvalidSyscall`] if this is not a `Lend` or `LendMut` message.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn return_memory(mut self, offset: usize, valid: usize) -> Result<(), Error> {
+        if self.kind != MessageKind::Lend && self.kind != MessageKind::LendMut {
+            return Err(Error::InvalidSyscall);
+        }
+        self.answered = true;
+        // SAFETY: The envelope is consumed, so the buffer can no longer be accessed.
+        unsafe { return_memory(self.sender, self.raw_buffer(), offset, valid) }
+    }
+
+    fn is_memory(&self) -> bool {
+        matches!(self.kind, MessageKind::LendMut | MessageKind::Lend | MessageKind::Move)
+    }
+
+    fn raw_buffer(&self) -> *mut [u8] {
+        crate::ptr::slice_from_raw_parts_mut(self.args[0] as *mut u8, self.args[1])
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl fmt::Debug for MessageEnvelope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageEnvelope")
+            .field("sender", &self.sender)
+            .field("kind", &self.kind)
+            .field("opcode", &self.opcode)
+            .field("args", &self.args)
+            .finish()
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl Drop for MessageEnvelope {
+    fn drop(&mut self) {
+        if self.answered {
+            return;
+        }
+        // Errors are ignored, since there is nobody left to report them to.
+        match self.kind {
+            MessageKind::Scalar => {}
+            MessageKind::BlockingScalar => {
+                return_scalar(self.sender, [0; 5]).ok();
+            }
+            // SAFETY: The envelope is going away, so the buffer can no longer be accessed.
+            MessageKind::Lend | MessageKind::LendMut => unsafe {
+                return_memory(self.sender, self.raw_buffer(), 0, 0).ok();
+            },
+            // SAFETY: As above. Moved memory belongs to us, so it is freed instead.
+            MessageKind::Move => unsafe {
+                unmap_memory(self.raw_buffer()).ok();
+            },
+        }
+    }
+}
+
+/// A zeroed, page-aligned buffer whose length is a multiple of the page size,
+/// suitable for lending to another process.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct PageBuffer {
+    data: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: `PageBuffer` uniquely owns its allocation.
+#[stable(feature = "rust1", since = "1.0.0")]
+unsafe impl Send for PageBuffer {}
+#[stable(feature = "rust1", since = "1.0.0")]
+unsafe impl Sync for PageBuffer {}
+
+impl PageBuffer {
+    /// Allocate a buffer of at least `len` bytes. The length is rounded up to a
+    /// whole number of pages, and is never less than one page.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn new(len: usize) -> PageBuffer {
+        let len = len.max(1).next_multiple_of(PAGE_SIZE);
+        let layout = Self::layout(len);
+        // SAFETY: The layout has a nonzero size.
+        let Some(data) = NonNull::new(unsafe { alloc_zeroed(layout) }) else {
+            handle_alloc_error(layout)
+        };
+        PageBuffer { data, len }
+    }
+
+    fn layout(len: usize) -> Layout {
+        Layout::from_size_align(len, PAGE_SIZE).unwrap()
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl Deref for PageBuffer {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        // SAFETY: `data` points to `len` initialized bytes owned by this buffer.
+        unsafe { crate::slice::from_raw_parts(self.data.as_ptr(), self.len) }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl DerefMut for PageBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: As above, and `&mut self` guarantees exclusive access.
+        unsafe { crate::slice::from_raw_parts_mut(self.data.as_ptr(), self.len) }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl Drop for PageBuffer {
+    fn drop(&mut self) {
+        // SAFETY: The allocation was made in `new()` with this same layout.
+        unsafe { dealloc(self.data.as_ptr(), Self::layout(self.len)) }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl fmt::Debug for PageBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PageBuffer").field("len", &self.len).finish_non_exhaustive()
+    }
+}
+
+/// Connect to the server at `address`, blocking until it exists.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn connect(address: ServerAddress) -> Result<Connection, Error> {
+    ffi::connect(address)
+}
+
+/// Connect to the server at `address`, returning `None` if it does not exist.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn try_connect(address: ServerAddress) -> Result<Option<Connection>, Error> {
+    ffi::try_connect(address)
+}
+
+/// Lend `buffer` to a server as read-only memory, blocking until it is returned.
+/// The server's `offset` and `valid` response values are returned.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn lend(
+    connection: Connection,
+    opcode: usize,
+    buffer: &PageBuffer,
+    arg1: usize,
+    arg2: usize,
+) -> Result<(usize, usize), Error> {
+    ffi::lend(connection, opcode, buffer, arg1, arg2)
+}
+
+/// Lend `buffer` to a server, allowing it to modify the contents, and block
+/// until it is returned. The server's `offset` and `valid` response values are returned.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn lend_mut(
+    connection: Connection,
+    opcode: usize,
+    buffer: &mut PageBuffer,
+    arg1: usize,
+    arg2: usize,
+) -> Result<(usize, usize), Error> {
+    ffi::lend_mut(connection, opcode, buffer, arg1, arg2)
+}
+
+/// Send a message containing an opcode and four words to a server without
+/// waiting for it to be handled. `args[0]` is the opcode.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn scalar(connection: Connection, args: [usize; 5]) -> Result<(), Error> {
+    ffi::scalar(connection, args)
+}
+
+/// Send a message containing an opcode and four words to a server, and block
+/// until it responds with up to five words. `args[0]` is the opcode.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn blocking_scalar(connection: Connection, args: [usize; 5]) -> Result<[usize; 5], Error> {
+    ffi::blocking_scalar(connection, args)
+}
@@ -2,12 +2,18 @@
 #![doc(cfg(target_os = "xous"))]
 
 pub mod ffi;
+pub mod ipc;
 
 #[stable(feature = "rust1", since = "1.0.0")]
 pub mod services;
 
+pub mod alloc;
 pub mod fs;
+pub mod net;
 pub mod path;
+pub mod senres;
+pub mod thread;
+pub mod time;
 
 /// A prelude for conveniently writing platform-specific code.
 ///
@@ -0,0 +1,341 @@
+//! Xous-specific networking functionality.
+//!
+//! [`TcpStreamExt`] adds socket options that have no portable equivalent, and
+//! [`lookup`] and [`lookup_with_ttl`] give more control over name resolution
+//! than [`ToSocketAddrs`](crate::net::ToSocketAddrs).
+//!
+//! Sockets may be put into nonblocking mode with `set_nonblocking()`, but that
+//! alone leaves no way to find out when an operation would succeed other than
+//! trying it again. [`poll`] asks the network server to wait on a whole set of
+//! sockets at once, which is what an event loop needs.
+
+#![stable(feature = "rust1", since = "1.0.0")]
+
+use crate::fmt;
+use crate::io;
+use crate::net::{IpAddr, TcpListener, TcpStream, UdpSocket};
+use crate::ops::{BitOr, BitOrAssign};
+use crate::os::xous::services::DnsQueryType;
+use crate::sys::net as net_imp;
+use crate::sys_common::AsInner;
+use crate::time::Duration;
+
+/// A set of events that a socket may become ready for.
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct Interest(u8);
+
+impl Interest {
+    /// The empty set.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub const NONE: Interest = Interest(0);
+
+    /// Data may be read from the socket without blocking, or the peer has
+    /// closed the connection.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub const READABLE: Interest = Interest(net_imp::POLL_READABLE);
+
+    /// Data may be written to the socket without blocking.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub const WRITABLE: Interest = Interest(net_imp::POLL_WRITABLE);
+
+    /// A [`TcpListener`] has a connection waiting in [`accept`](TcpListener::accept).
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub const ACCEPT: Interest = Interest(net_imp::POLL_ACCEPT);
+
+    /// Returns `true` if this set contains [`Interest::READABLE`].
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn is_readable(self) -> bool {
+        self.0 & net_imp::POLL_READABLE != 0
+    }
+
+    /// Returns `true` if this set contains [`Interest::WRITABLE`].
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn is_writable(self) -> bool {
+        self.0 & net_imp::POLL_WRITABLE != 0
+    }
+
+    /// Returns `true` if this set contains [`Interest::ACCEPT`].
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn is_acceptable(self) -> bool {
+        self.0 & net_imp::POLL_ACCEPT != 0
+    }
+
+    /// Returns `true` if this set contains no events.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl BitOr for Interest {
+    type Output = Interest;
+    fn bitor(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl BitOrAssign for Interest {
+    fn bitor_assign(&mut self, other: Interest) {
+        self.0 |= other.0;
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl fmt::Debug for Interest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut set = f.debug_set();
+        if self.is_readable() {
+            set.entry(&format_args!("READABLE"));
+        }
+        if self.is_writable() {
+            set.entry(&format_args!("WRITABLE"));
+        }
+        if self.is_acceptable() {
+            set.entry(&format_args!("ACCEPT"));
+        }
+        set.finish()
+    }
+}
+
+mod private {
+    pub trait Sealed {
+        fn poll_source(&self) -> crate::sys::net::PollSource;
+    }
+}
+
+/// A socket that may be waited on with [`poll`].
+///
+/// This trait is sealed and is implemented for [`TcpStream`], [`TcpListener`]
+/// and [`UdpSocket`].
+#[stable(feature = "rust1", since = "1.0.0")]
+pub trait Pollable: private::Sealed {}
+
+impl private::Sealed for TcpStream {
+    fn poll_source(&self) -> net_imp::PollSource {
+        self.as_inner().poll_source()
+    }
+}
+
+impl private::Sealed for TcpListener {
+    fn poll_source(&self) -> net_imp::PollSource {
+        self.as_inner().poll_source()
+    }
+}
+
+impl private::Sealed for UdpSocket {
+    fn poll_source(&self) -> net_imp::PollSource {
+        self.as_inner().poll_source()
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl Pollable for TcpStream {}
+#[stable(feature = "rust1", since = "1.0.0")]
+impl Pollable for TcpListener {}
+#[stable(feature = "rust1", since = "1.0.0")]
+impl Pollable for UdpSocket {}
+
+/// A socket to wait on, along with the events that are of interest.
+///
+/// After a call to [`poll`], [`ready`](PollFd::ready) returns the events that
+/// the socket is ready for.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct PollFd<'a> {
+    socket: &'a dyn Pollable,
+    interest: Interest,
+    ready: Interest,
+}
+
+impl<'a> PollFd<'a> {
+    /// Wait for `socket` to become ready for any of the events in `interest`.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn new(socket: &'a dyn Pollable, interest: Interest) -> PollFd<'a> {
+        PollFd { socket, interest, ready: Interest::NONE }
+    }
+
+    /// Returns the events that were asked for.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn interest(&self) -> Interest {
+        self.interest
+    }
+
+    /// Changes the events to wait for on the next call to [`poll`].
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn set_interest(&mut self, interest: Interest) {
+        self.interest = interest;
+    }
+
+    /// Returns the events that the socket was ready for when the last call to
+    /// [`poll`] returned. This is always a subset of the interest set.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn ready(&self) -> Interest {
+        self.ready
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl fmt::Debug for PollFd<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PollFd")
+            .field("interest", &self.interest)
+            .field("ready", &self.ready)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Waits until at least one of `fds` is ready for one of the events it is
+/// interested in, or until `timeout` has passed.
+///
+/// A `timeout` of `None` waits forever, and a zero timeout returns at once
+/// with the sockets that are ready right now. Returns the number of sockets
+/// that are ready, which is zero if the timeout expired. The events that each
+/// socket is ready for are available from [`PollFd::ready`].
+///
+/// At most 512 sockets may be waited on at once.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{TcpListener, TcpStream};
+/// use std::os::xous::net::{poll, Interest, PollFd};
+/// use std::time::Duration;
+///
+/// let listener = TcpListener::bind("0.0.0.0:8080").unwrap();
+/// let stream = TcpStream::connect("10.0.0.1:80").unwrap();
+/// let mut fds = [
+///     PollFd::new(&listener, Interest::ACCEPT),
+///     PollFd::new(&stream, Interest::READABLE | Interest::WRITABLE),
+/// ];
+/// if poll(&mut fds, Some(Duration::from_secs(1))).unwrap() > 0 {
+///     if fds[0].ready().is_acceptable() {
+///         let (_connection, addr) = listener.accept().unwrap();
+///         println!("new connection from {addr}");
+///     }
+/// }
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn poll(fds: &mut [PollFd<'_>], timeout: Option<Duration>) -> io::Result<usize> {
+    let sources: Vec<(net_imp::PollSource, u8)> =
+        fds.iter().map(|fd| (fd.socket.poll_source(), fd.interest.0)).collect();
+    let mut ready = vec![0u8; fds.len()];
+    let count = net_imp::poll(&sources, &mut ready, timeout)?;
+    for (fd, ready) in fds.iter_mut().zip(ready) {
+        fd.ready = Interest(ready);
+    }
+    Ok(count)
+}
+
+/// Xous-specific extensions to [`TcpStream`].
+#[stable(feature = "rust1", since = "1.0.0")]
+pub trait TcpStreamExt {
+    /// Enables TCP keepalive on this connection, sending a probe whenever the
+    /// connection has been idle for `interval`. `None` disables keepalive.
+    ///
+    /// Without keepalive, a connection whose peer goes away without closing it
+    /// is only noticed the next time data is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::TcpStream;
+    /// use std::os::xous::net::TcpStreamExt;
+    /// use std::time::Duration;
+    ///
+    /// let stream = TcpStream::connect("10.0.0.1:443").unwrap();
+    /// stream.set_keepalive(Some(Duration::from_secs(60))).unwrap();
+    /// assert_eq!(stream.keepalive().unwrap(), Some(Duration::from_secs(60)));
+    /// ```
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn set_keepalive(&self, interval: Option<Duration>) -> io::Result<()>;
+
+    /// Returns the keepalive interval of this connection, or `None` if
+    /// keepalive is disabled.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn keepalive(&self) -> io::Result<Option<Duration>>;
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl TcpStreamExt for TcpStream {
+    fn set_keepalive(&self, interval: Option<Duration>) -> io::Result<()> {
+        self.as_inner().set_keepalive(interval)
+    }
+
+    fn keepalive(&self) -> io::Result<Option<Duration>> {
+        self.as_inner().keepalive()
+    }
+}
+
+/// The kinds of address records that [`lookup`] asks for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[stable(feature = "rust1", since = "1.0.0")]
+pub enum RecordType {
+    /// Only IPv4 addresses.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    A,
+    /// Only IPv6 addresses.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    Aaaa,
+    /// Both IPv4 and IPv6 addresses. This is what
+    /// [`ToSocketAddrs`](crate::net::ToSocketAddrs) uses.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    Both,
+}
+
+impl RecordType {
+    fn query_type(self) -> DnsQueryType {
+        match self {
+            RecordType::A => DnsQueryType::V4,
+            RecordType::Aaaa => DnsQueryType::V6,
+            RecordType::Both => DnsQueryType::Any,
+        }
+    }
+}
+
+/// Resolves `host` to a list of addresses.
+///
+/// Failures are reported with distinct error kinds, so that the caller can
+/// tell them apart:
+///
+/// * [`io::ErrorKind::NotFound`] if the name does not exist.
+/// * [`io::ErrorKind::TimedOut`] if the DNS server did not answer in time.
+/// * [`io::ErrorKind::NetworkUnreachable`] if no DNS server is configured.
+/// * [`io::ErrorKind::NetworkDown`] if there is no network connection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::ErrorKind;
+/// use std::os::xous::net::{lookup, RecordType};
+///
+/// match lookup("betrusted.io", RecordType::A) {
+///     Ok(addrs) => println!("found {addrs:?}"),
+///     Err(e) if e.kind() == ErrorKind::NotFound => println!("no such host"),
+///     Err(e) => println!("unable to look up host: {e}"),
+/// }
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn lookup(host: &str, record_type: RecordType) -> io::Result<Vec<IpAddr>> {
+    net_imp::lookup_addresses(host, record_type.query_type())
+}
+
+/// Resolves `host` to a list of addresses, along with how long each of them
+/// may be cached for.
+///
+/// Errors are reported in the same way as for [`lookup`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::net::{lookup_with_ttl, RecordType};
+///
+/// for (addr, ttl) in lookup_with_ttl("betrusted.io", RecordType::Both).unwrap() {
+///     println!("{addr} is valid for {} seconds", ttl.as_secs());
+/// }
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn lookup_with_ttl(host: &str, record_type: RecordType) -> io::Result<Vec<(IpAddr, Duration)>> {
+    net_imp::lookup_with_ttl(host, record_type.query_type())
+}
@@ -0,0 +1,689 @@
+//! Senres is a versioned, page-aligned message format for sending structured
+//! data between Xous processes.
+//!
+//! A message starts with a version magic number followed by a fourcc that
+//! identifies what the message contains. Values are then appended one after
+//! another, each aligned to its natural alignment. Strings and slices are
+//! prefixed with their length as a `u32`.
+//!
+//! Every read checks that the value lies entirely within the message, so a
+//! malformed or truncated message produces an error rather than a panic.
+
+#![stable(feature = "rust1", since = "1.0.0")]
+#![allow(elided_lifetimes_in_paths)]
+
+use crate::os::xous::ffi::{Connection, Error};
+use core::cell::Cell;
+use core::convert::TryInto;
+
+/// Senres V1 always begins with the number 0x344cb6ca to indicate it's valid.
+/// This number will change on subsequent versions.
+const SENRES_V1_MAGIC: u32 = 0x344cb6ca;
+
+/// A struct to send and receive data. This struct must be page-aligned
+/// in order to be sendable across processes.
+#[stable(feature = "rust1", since = "1.0.0")]
+#[repr(C, align(4096))]
+pub struct Stack<const N: usize = 4096> {
+    data: [u8; N],
+}
+
+/// A version of the message on the receiving side, reconstituted from
+/// a slice from a message.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct Message<'a> {
+    data: &'a [u8],
+}
+
+/// A version of the message on the receiving side, reconstituted from
+/// a slice from a message. The receiver may write a response into it.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct MutableMessage<'a> {
+    data: &'a mut [u8],
+}
+
+impl<'a> Message<'a> {
+    /// Wrap the buffer of a received `Lend` message, such as the one returned by
+    /// [`crate::os::xous::ipc::MessageEnvelope::buffer`].
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn from_slice(data: &'a [u8]) -> Message<'a> {
+        Message { data }
+    }
+
+    /// Wrap the buffer of a received `LendMut` message, such as the one returned by
+    /// [`crate::os::xous::ipc::MessageEnvelope::buffer_mut`].
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn from_mut_slice(data: &'a mut [u8]) -> MutableMessage<'a> {
+        MutableMessage { data }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<'a> SenresMut for MutableMessage<'a> {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.data
+    }
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.data.as_mut_ptr()
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<'a> Senres for MutableMessage<'a> {
+    fn as_slice(&self) -> &[u8] {
+        self.data
+    }
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+    fn as_ptr(&self) -> *const u8 {
+        self.data.as_ptr()
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<'a> Senres for Message<'a> {
+    fn as_slice(&self) -> &[u8] {
+        self.data
+    }
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+    fn as_ptr(&self) -> *const u8 {
+        self.data.as_ptr()
+    }
+    fn can_create_writer(&self) -> bool {
+        false
+    }
+}
+
+/// A buffer that holds a Senres message.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub trait Senres {
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn as_slice(&self) -> &[u8];
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn as_ptr(&self) -> *const u8;
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn len(&self) -> usize;
+
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn can_create_writer(&self) -> bool {
+        true
+    }
+
+    /// Begin reading the message. Returns `None` if the buffer does not contain
+    /// a Senres V1 message, or if the message is not tagged with `fourcc`.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn reader(&self, fourcc: [u8; 4]) -> Option<Reader<Self>>
+    where
+        Self: core::marker::Sized,
+    {
+        let reader = Reader { backing: self, offset: core::cell::Cell::new(0) };
+        if SENRES_V1_MAGIC != reader.try_get_from().ok()? {
+            return None;
+        }
+        let target_fourcc: [u8; 4] = reader.try_get_from().ok()?;
+        if target_fourcc != fourcc {
+            return None;
+        }
+        Some(reader)
+    }
+
+    /// Lend this message to a server as read-only memory, returning the
+    /// server's `offset` and `valid` response values.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn lend(&self, connection: Connection, opcode: usize) -> Result<(usize, usize), Error> {
+        crate::os::xous::ffi::lend(connection, opcode, self.as_slice(), 0, 0)
+    }
+}
+
+/// A buffer that a Senres message can be written into.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub trait SenresMut: Senres {
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn as_mut_slice(&mut self) -> &mut [u8];
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn as_mut_ptr(&mut self) -> *mut u8;
+
+    /// Begin a new message tagged with `fourcc`, replacing any existing contents.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn writer(&mut self, fourcc: [u8; 4]) -> Option<Writer<Self>>
+    where
+        Self: core::marker::Sized,
+    {
+        if !self.can_create_writer() {
+            return None;
+        }
+        let mut writer = Writer { backing: self, offset: 0 };
+        writer.append(SENRES_V1_MAGIC);
+        writer.append(fourcc);
+        Some(writer)
+    }
+
+    /// Lend this message to a server, which may write a response into it. The
+    /// server's `offset` and `valid` response values are returned.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn lend_mut(&mut self, connection: Connection, opcode: usize) -> Result<(usize, usize), Error> {
+        crate::os::xous::ffi::lend_mut(connection, opcode, self.as_mut_slice(), 0, 0)
+    }
+}
+
+/// Appends values to a message.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct Writer<'a, Backing: SenresMut> {
+    backing: &'a mut Backing,
+    offset: usize,
+}
+
+/// Space reserved in a message by [`Writer::delayed_append`], to be filled in
+/// later by [`Writer::do_delayed_append`].
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct DelayedWriter<Backing: SenresMut, T: SenSer<Backing>> {
+    offset: usize,
+    _kind: core::marker::PhantomData<T>,
+    _backing: core::marker::PhantomData<Backing>,
+}
+
+/// Reads values out of a message, in the order they were written.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub struct Reader<'a, Backing: Senres> {
+    backing: &'a Backing,
+    offset: Cell<usize>,
+}
+
+/// Types that can be written into a message.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub trait SenSer<Backing: SenresMut> {
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn append_to(&self, senres: &mut Writer<Backing>);
+}
+
+/// Types that can be read out of a message by value.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub trait RecDes<Backing: Senres> {
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()>
+    where
+        Self: core::marker::Sized;
+}
+
+/// Types that can be borrowed directly out of a message without copying.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub trait RecDesRef<'a, Backing: Senres> {
+    #[stable(feature = "rust1", since = "1.0.0")]
+    fn try_get_ref_from(senres: &'a Reader<Backing>) -> Result<&'a Self, ()>;
+}
+
+impl<const N: usize> Stack<N> {
+    /// Ensure that `N` is a multiple of 4096. This constant should
+    /// be evaluated in the constructor function.
+    const CHECK_ALIGNED: () = if N & 4095 != 0 {
+        panic!("Senres size must be a multiple of 4096")
+    };
+
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub const fn new() -> Self {
+        // Ensure the `N` that was specified is a multiple of 4096
+        #[allow(clippy::no_effect, clippy::let_unit_value)]
+        let _ = Self::CHECK_ALIGNED;
+        Stack { data: [0u8; N] }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<const N: usize> SenresMut for Stack<N> {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.data.as_mut_slice()
+    }
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        &mut self.data as *mut _ as *mut u8
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<const N: usize> Senres for Stack<N> {
+    fn as_slice(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+    fn len(&self) -> usize {
+        N
+    }
+    fn as_ptr(&self) -> *const u8 {
+        &self.data as *const _ as *const u8
+    }
+}
+
+impl<'a, Backing: SenresMut> Writer<'a, Backing> {
+    /// Append `other` to the message. Data that does not fit in the buffer is
+    /// silently dropped, and will cause the receiver's reads to fail.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn append<T: SenSer<Backing>>(&mut self, other: T) {
+        other.append_to(self);
+    }
+
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn delayed_append<T: SenSer<Backing>>(&mut self) -> DelayedWriter<Backing, T> {
+        let delayed_writer = DelayedWriter {
+            offset: self.offset,
+            _backing: core::marker::PhantomData::<Backing>,
+            _kind: core::marker::PhantomData::<T>,
+        };
+        self.offset += core::mem::size_of::<T>();
+        delayed_writer
+    }
+
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn do_delayed_append<T: SenSer<Backing>>(
+        &mut self,
+        delayed_writer: DelayedWriter<Backing, T>,
+        other: T,
+    ) {
+        let current_offset = self.offset;
+        self.offset = delayed_writer.offset;
+        other.append_to(self);
+        if self.offset != delayed_writer.offset + core::mem::size_of::<T>() {
+            panic!("writer incorrectly increased offset");
+        }
+        self.offset = current_offset;
+    }
+
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn align_to(&mut self, alignment: usize) {
+        while self.offset & (alignment - 1) != 0 {
+            self.offset += 1;
+        }
+    }
+
+    /// Copy `bytes` into the message at the current offset, dropping anything
+    /// that doesn't fit.
+    fn append_bytes(&mut self, bytes: &[u8]) {
+        let buffer = self.backing.as_mut_slice();
+        let start = self.offset.min(buffer.len());
+        for (src, dest) in bytes.iter().zip(buffer[start..].iter_mut()) {
+            *dest = *src;
+            self.offset += 1;
+        }
+    }
+}
+
+impl<'a, Backing: Senres> Reader<'a, Backing> {
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn try_get_from<T: RecDes<Backing>>(&self) -> Result<T, ()> {
+        T::try_get_from(self)
+    }
+
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn try_get_ref_from<T: RecDesRef<'a, Backing> + ?Sized>(&'a self) -> Result<&T, ()> {
+        T::try_get_ref_from(self)
+    }
+
+    fn align_to(&self, alignment: usize) {
+        while self.offset.get() & (alignment - 1) != 0 {
+            self.offset.set(self.offset.get() + 1);
+        }
+    }
+
+    /// Borrow the next `len` bytes of the message and advance past them, or
+    /// return an error if that would run off the end of the message.
+    fn take_bytes(&self, len: usize) -> Result<&'a [u8], ()> {
+        let offset = self.offset.get();
+        let end = offset.checked_add(len).ok_or(())?;
+        let bytes = self.backing.as_slice().get(offset..end).ok_or(())?;
+        self.offset.set(end);
+        Ok(bytes)
+    }
+}
+
+macro_rules! primitive_impl {
+    ($SelfT:ty) => {
+        #[stable(feature = "rust1", since = "1.0.0")]
+        impl<Backing: SenresMut> SenSer<Backing> for $SelfT {
+            fn append_to(&self, senres: &mut Writer<Backing>) {
+                senres.align_to(core::mem::align_of::<Self>());
+                senres.append_bytes(&self.to_le_bytes());
+            }
+        }
+
+        #[stable(feature = "rust1", since = "1.0.0")]
+        impl<Backing: Senres> RecDes<Backing> for $SelfT {
+            fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()> {
+                senres.align_to(core::mem::align_of::<Self>());
+                let bytes = senres.take_bytes(core::mem::size_of::<Self>())?;
+                Ok(Self::from_le_bytes(bytes.try_into().unwrap()))
+            }
+        }
+
+        #[stable(feature = "rust1", since = "1.0.0")]
+        impl<'a, Backing: Senres> RecDesRef<'a, Backing> for [$SelfT] {
+            fn try_get_ref_from(senres: &'a Reader<Backing>) -> Result<&'a Self, ()> {
+                let len = senres.try_get_from::<u32>()? as usize;
+                // The writer only pads before the first element, so an empty
+                // slice takes up no space at all.
+                if len == 0 {
+                    return Ok(&[]);
+                }
+                senres.align_to(core::mem::align_of::<$SelfT>());
+                let bytes = senres
+                    .take_bytes(len.checked_mul(core::mem::size_of::<$SelfT>()).ok_or(())?)?;
+                // Values are stored little-endian, which matches the layout in
+                // memory only on little-endian targets.
+                if cfg!(target_endian = "big")
+                    || bytes.as_ptr().align_offset(core::mem::align_of::<$SelfT>()) != 0
+                {
+                    return Err(());
+                }
+                // SAFETY: `bytes` is in bounds and suitably aligned for `len` values,
+                // and every bit pattern is a valid integer.
+                Ok(unsafe { core::slice::from_raw_parts(bytes.as_ptr() as *const $SelfT, len) })
+            }
+        }
+    };
+}
+
+/// `usize` and `isize` are always sent as 64-bit values, so that processes
+/// with different pointer widths can talk to each other.
+macro_rules! pointer_sized_impl {
+    ($SelfT:ty, $WireT:ty) => {
+        #[stable(feature = "rust1", since = "1.0.0")]
+        impl<Backing: SenresMut> SenSer<Backing> for $SelfT {
+            fn append_to(&self, senres: &mut Writer<Backing>) {
+                senres.append(*self as $WireT);
+            }
+        }
+
+        #[stable(feature = "rust1", since = "1.0.0")]
+        impl<Backing: Senres> RecDes<Backing> for $SelfT {
+            fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()> {
+                senres.try_get_from::<$WireT>()?.try_into().or(Err(()))
+            }
+        }
+    };
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<Backing: SenresMut> SenSer<Backing> for bool {
+    fn append_to(&self, senres: &mut Writer<Backing>) {
+        senres.append(if *self { 1u8 } else { 0u8 });
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<Backing: Senres> RecDes<Backing> for bool {
+    fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()> {
+        match senres.try_get_from::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(()),
+        }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<T: SenSer<Backing>, Backing: SenresMut> SenSer<Backing> for Option<T> {
+    fn append_to(&self, senres: &mut Writer<Backing>) {
+        if let Some(val) = self {
+            senres.append(1u8);
+            val.append_to(senres);
+        } else {
+            senres.append(0u8);
+        }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<T: RecDes<Backing>, Backing: Senres> RecDes<Backing> for Option<T> {
+    fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()> {
+        match senres.try_get_from::<u8>()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::try_get_from(senres)?)),
+            _ => Err(()),
+        }
+    }
+}
+
+primitive_impl! {u8}
+primitive_impl! {i8}
+primitive_impl! {u16}
+primitive_impl! {i16}
+primitive_impl! {u32}
+primitive_impl! {i32}
+primitive_impl! {u64}
+primitive_impl! {i64}
+primitive_impl! {u128}
+primitive_impl! {i128}
+pointer_sized_impl! {usize, u64}
+pointer_sized_impl! {isize, i64}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<T: SenSer<Backing>, Backing: SenresMut> SenSer<Backing> for &[T] {
+    fn append_to(&self, senres: &mut Writer<Backing>) {
+        senres.append(self.len() as u32);
+        for entry in self.iter() {
+            entry.append_to(senres)
+        }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<T: SenSer<Backing>, Backing: SenresMut> SenSer<Backing> for Vec<T> {
+    fn append_to(&self, senres: &mut Writer<Backing>) {
+        senres.append(self.as_slice());
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<T: RecDes<Backing>, Backing: Senres> RecDes<Backing> for Vec<T> {
+    fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()> {
+        let len = senres.try_get_from::<u32>()? as usize;
+        // Every element takes up at least one byte, so don't let a bogus length
+        // trigger a huge allocation.
+        if len > senres.backing.len().saturating_sub(senres.offset.get()) {
+            return Err(());
+        }
+        let mut output = Vec::with_capacity(len);
+        for _ in 0..len {
+            output.push(T::try_get_from(senres)?);
+        }
+        Ok(output)
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<T: SenSer<Backing>, Backing: SenresMut, const N: usize> SenSer<Backing> for [T; N] {
+    fn append_to(&self, senres: &mut Writer<Backing>) {
+        senres.align_to(core::mem::align_of::<Self>());
+        for entry in self.iter() {
+            entry.append_to(senres)
+        }
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<T: RecDes<Backing>, Backing: Senres, const N: usize> RecDes<Backing> for [T; N] {
+    fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()> {
+        senres.align_to(core::mem::align_of::<Self>());
+
+        // See https://github.com/rust-lang/rust/issues/61956 for why this
+        // is awful
+        let mut output: [core::mem::MaybeUninit<T>; N] =
+            unsafe { core::mem::MaybeUninit::uninit().assume_init() };
+        for index in 0..N {
+            match T::try_get_from(senres) {
+                Ok(val) => {
+                    output[index].write(val);
+                }
+                Err(()) => {
+                    // Don't leak the elements that were already read
+                    for initialized in &mut output[..index] {
+                        unsafe { initialized.assume_init_drop() };
+                    }
+                    return Err(());
+                }
+            }
+        }
+
+        // Using &mut as an assertion of unique "ownership"
+        let ptr = &mut output as *mut _ as *mut [T; N];
+        let res = unsafe { ptr.read() };
+        core::mem::forget(output);
+        Ok(res)
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<Backing: SenresMut> SenSer<Backing> for str {
+    fn append_to(&self, senres: &mut Writer<Backing>) {
+        senres.append(self.len() as u32);
+        senres.append_bytes(self.as_bytes());
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<Backing: SenresMut> SenSer<Backing> for &str {
+    fn append_to(&self, senres: &mut Writer<Backing>) {
+        (**self).append_to(senres)
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<Backing: SenresMut> SenSer<Backing> for String {
+    fn append_to(&self, senres: &mut Writer<Backing>) {
+        self.as_str().append_to(senres)
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<Backing: Senres> RecDes<Backing> for String {
+    fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()> {
+        let val: &str = <str as RecDesRef<'_, Backing>>::try_get_ref_from(senres)?;
+        Ok(val.to_owned())
+    }
+}
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<'a, Backing: Senres> RecDesRef<'a, Backing> for str {
+    fn try_get_ref_from(senres: &'a Reader<Backing>) -> Result<&'a Self, ()> {
+        let len = senres.try_get_from::<u32>()? as usize;
+        let offset = senres.offset.get();
+        let bytes = senres.take_bytes(len)?;
+        core::str::from_utf8(bytes).or_else(|_| {
+            // Leave the reader where it was so the caller may try something else
+            senres.offset.set(offset);
+            Err(())
+        })
+    }
+}
+
+macro_rules! tuple_impl {
+    ($($name:ident)+) => {
+        #[stable(feature = "rust1", since = "1.0.0")]
+        impl<Backing: SenresMut, $($name: SenSer<Backing>),+> SenSer<Backing> for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn append_to(&self, senres: &mut Writer<Backing>) {
+                let ($($name,)+) = self;
+                $($name.append_to(senres);)+
+            }
+        }
+
+        #[stable(feature = "rust1", since = "1.0.0")]
+        impl<Backing: Senres, $($name: RecDes<Backing>),+> RecDes<Backing> for ($($name,)+) {
+            fn try_get_from(senres: &Reader<Backing>) -> Result<Self, ()> {
+                Ok(($($name::try_get_from(senres)?,)+))
+            }
+        }
+    };
+}
+
+tuple_impl! { A }
+tuple_impl! { A B }
+tuple_impl! { A B C }
+tuple_impl! { A B C D }
+tuple_impl! { A B C D E }
+tuple_impl! { A B C D E F }
+tuple_impl! { A B C D E F G }
+tuple_impl! { A B C D E F G H }
+
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<const N: usize> Default for Stack<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn smoke_test() {
+    let mut sr1 = Stack::<4096>::new();
+    let sr3 = Stack::<8192>::new();
+    // The default size is a single page
+    let sr5: Stack = Stack::new();
+
+    assert_eq!(core::mem::size_of_val(&sr1), 4096);
+    assert_eq!(core::mem::size_of_val(&sr3), 8192);
+    assert_eq!(core::mem::size_of_val(&sr5), 4096);
+
+    {
+        let mut writer = sr1.writer(*b"test").unwrap();
+        writer.append(16777215u32);
+        writer.append(u64::MAX);
+        writer.append("Hello, world!");
+        writer.append("String2");
+        writer.append::<Option<u32>>(None);
+        writer.append::<Option<u32>>(Some(42));
+        writer.append(96u8);
+        writer.append([1i32, 2, 3, 4, 5].as_slice());
+        writer.append([5u8, 4, 3, 2].as_slice());
+        writer.append([5u16, 4, 2]);
+        writer.append(["Hi", "There", "123456789"]);
+    }
+
+    let reader = sr1.reader(*b"test").expect("couldn't get reader");
+    assert_eq!(reader.try_get_from::<u32>(), Ok(16777215));
+    assert_eq!(reader.try_get_from::<u64>(), Ok(u64::MAX));
+    assert_eq!(reader.try_get_ref_from::<str>(), Ok("Hello, world!"));
+    assert_eq!(reader.try_get_from::<String>().as_deref(), Ok("String2"));
+    assert_eq!(reader.try_get_from::<Option<u32>>(), Ok(None));
+    assert_eq!(reader.try_get_from::<Option<u32>>(), Ok(Some(42)));
+    assert_eq!(reader.try_get_from::<u8>(), Ok(96));
+    assert_eq!(reader.try_get_ref_from::<[i32]>(), Ok(&[1, 2, 3, 4, 5][..]));
+    assert_eq!(reader.try_get_ref_from::<[u8]>(), Ok(&[5, 4, 3, 2][..]));
+    assert_eq!(reader.try_get_from::<[u16; 3]>(), Ok([5, 4, 2]));
+    assert_eq!(
+        reader.try_get_from::<[String; 3]>(),
+        Ok(["Hi".to_owned(), "There".to_owned(), "123456789".to_owned()])
+    );
+}
+
+#[test]
+fn malformed_messages() {
+    let mut sr = Stack::<4096>::new();
+    assert!(sr.reader(*b"test").is_none(), "a zeroed buffer has no magic number");
+
+    {
+        let mut writer = sr.writer(*b"test").unwrap();
+        writer.append((1usize, (-2isize, Some(u128::MAX)), vec![(true, "nested".to_owned())]));
+        // A string whose length runs past the end of the buffer
+        writer.append(u32::MAX);
+    }
+    assert!(sr.reader(*b"nope").is_none(), "the fourcc must match");
+
+    let reader = sr.reader(*b"test").unwrap();
+    let val: (usize, (isize, Option<u128>), Vec<(bool, String)>) = reader.try_get_from().unwrap();
+    assert_eq!(val, (1, (-2, Some(u128::MAX)), vec![(true, "nested".to_owned())]));
+    assert!(reader.try_get_ref_from::<str>().is_err());
+}
+
+#[test]
+fn oversized_messages() {
+    let mut sr = Stack::<4096>::new();
+    {
+        let mut writer = sr.writer(*b"test").unwrap();
+        // Anything past the end of the buffer is dropped rather than panicking
+        writer.append([0x55u8; 5000].as_slice());
+    }
+
+    let reader = sr.reader(*b"test").unwrap();
+    assert!(reader.try_get_ref_from::<[u8]>().is_err());
+    let reader = sr.reader(*b"test").unwrap();
+    assert!(reader.try_get_from::<Vec<u8>>().is_err());
+}
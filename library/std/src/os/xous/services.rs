@@ -1,9 +1,12 @@
-use crate::os::xous::ffi::Connection;
+use crate::os::xous::ffi::{Connection, Error, ServerAddress};
 use core::sync::atomic::{AtomicU32, Ordering};
 
 mod dns;
 pub(crate) use dns::*;
 
+mod loader;
+pub(crate) use loader::*;
+
 mod log;
 pub(crate) use log::*;
 
@@ -21,7 +24,7 @@
 
 mod ns {
     const NAME_MAX_LENGTH: usize = 64;
-    use crate::os::xous::ffi::{lend_mut, Connection};
+    use crate::os::xous::ffi::{lend_mut, Connection, Error, ServerAddress};
     // By making this repr(C), the layout of this struct becomes well-defined
     // and no longer shifts around.
     // By marking it as `align(4096)` we define that it will be page-aligned,
@@ -77,6 +80,27 @@ pub fn connect_with_name_impl(name: &str, blocking: bool) -> Option<Connection>
         }
     }
 
+    /// Ask the name server to allocate a server address for `name`. The name
+    /// server picks the address, which the caller then uses to create the server.
+    pub fn register_with_name(name: &str) -> Result<ServerAddress, Error> {
+        if name.is_empty() || name.len() > NAME_MAX_LENGTH {
+            return Err(Error::InvalidString);
+        }
+        let mut request = ConnectRequest::new(name);
+        lend_mut(super::name_server(), 0 /* Register */, &mut request.data, 0, name.len())?;
+
+        // The result code comes first, followed by the four words of the address
+        let result = u32::from_le_bytes(request.data[0..4].try_into().unwrap());
+        if result != 0 {
+            return Err(Error::ServerExists);
+        }
+        let mut address = [0u32; 4];
+        for (dest, src) in address.iter_mut().zip(request.data[4..20].chunks_exact(4)) {
+            *dest = u32::from_le_bytes(src.try_into().unwrap());
+        }
+        Ok(address.into())
+    }
+
     pub fn connect_with_name(name: &str) -> Option<Connection> {
         connect_with_name_impl(name, true)
     }
@@ -108,6 +132,12 @@ pub fn try_connect(name: &str) -> Option<Connection> {
     ns::try_connect_with_name(name)
 }
 
+/// Register `name` with the name server, returning the address that the new
+/// server should be created with. Fails if the name is already taken.
+pub(crate) fn register_name(name: &str) -> Result<ServerAddress, Error> {
+    ns::register_with_name(name)
+}
+
 static NAME_SERVER_CONNECTION: AtomicU32 = AtomicU32::new(0);
 
 /// Return a `Connection` to the name server. If the name server has not been started,
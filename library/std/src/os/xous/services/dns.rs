@@ -2,14 +2,32 @@
 use crate::os::xous::services::connect;
 use core::sync::atomic::{AtomicU32, Ordering};
 
-#[repr(usize)]
+/// The address families that a lookup asks for. This is passed in the `offset`
+/// field of the request, where older servers ignore it and return both.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(crate) enum DnsQueryType {
+    Any = 0,
+    V4 = 1,
+    V6 = 2,
+}
+
 pub(crate) enum DnsLendMut {
-    RawLookup = 6,
+    /// Resolve the name in the buffer. Replies with an error flag and code, or
+    /// with a flag of 0 followed by the number of records and the records
+    /// themselves, each of which is a 4 or a 6 followed by the address.
+    RawLookup, /* 6 */
+
+    /// Like `RawLookup`, except that each record is followed by its TTL in
+    /// seconds as a little-endian `u32`.
+    StdLookupWithTtl, /* 30 */
 }
 
 impl Into<usize> for DnsLendMut {
     fn into(self) -> usize {
-        self as usize
+        match self {
+            DnsLendMut::RawLookup => 6,
+            DnsLendMut::StdLookupWithTtl => 30,
+        }
     }
 }
 
@@ -0,0 +1,88 @@
+use crate::io;
+use crate::os::xous::ffi::Connection;
+use crate::os::xous::services::try_connect;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Which side of a pipe an operation refers to
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum PipeEnd {
+    Read = 0,
+    Write = 1,
+}
+
+pub(crate) enum LoaderLendMut {
+    /// Launch a new process. The request is a `SpwQ` Senres message.
+    SpawnProcess, /* 1 */
+
+    /// Read bytes out of a pipe into the lent page.
+    PipeRead(u16 /* pipe */, bool /* nonblocking */), /* 2 */
+}
+
+pub(crate) enum LoaderLend {
+    /// Write the valid bytes of the lent page into a pipe.
+    PipeWrite(u16 /* pipe */), /* 3 */
+}
+
+pub(crate) enum LoaderBlockingScalar {
+    /// Block until the given process exits, and return its exit code.
+    WaitProcess(u32 /* pid */), /* 4 */
+
+    /// Return whether the given process has exited, and if so its exit code.
+    TryWaitProcess(u32 /* pid */), /* 5 */
+
+    /// Forcibly terminate a child process.
+    KillProcess(u32 /* pid */), /* 6 */
+
+    /// Allocate a new pipe, returning its ID.
+    CreatePipe, /* 7 */
+
+    /// Release one end of a pipe. The pipe is destroyed when both ends are closed.
+    ClosePipe(u16 /* pipe */, PipeEnd), /* 8 */
+}
+
+impl Into<usize> for LoaderLendMut {
+    fn into(self) -> usize {
+        match self {
+            LoaderLendMut::SpawnProcess => 1,
+            LoaderLendMut::PipeRead(pipe, nonblocking) => {
+                2 | ((pipe as usize) << 16) | if nonblocking { 0x8000 } else { 0 }
+            }
+        }
+    }
+}
+
+impl Into<usize> for LoaderLend {
+    fn into(self) -> usize {
+        match self {
+            LoaderLend::PipeWrite(pipe) => 3 | ((pipe as usize) << 16),
+        }
+    }
+}
+
+impl Into<[usize; 5]> for LoaderBlockingScalar {
+    fn into(self) -> [usize; 5] {
+        match self {
+            LoaderBlockingScalar::WaitProcess(pid) => [4, pid as usize, 0, 0, 0],
+            LoaderBlockingScalar::TryWaitProcess(pid) => [5, pid as usize, 0, 0, 0],
+            LoaderBlockingScalar::KillProcess(pid) => [6, pid as usize, 0, 0, 0],
+            LoaderBlockingScalar::CreatePipe => [7, 0, 0, 0, 0],
+            LoaderBlockingScalar::ClosePipe(pipe, end) => [8, pipe as usize, end as usize, 0, 0],
+        }
+    }
+}
+
+/// Return a `Connection` to the process loader server. This server is used for
+/// launching child processes and for the pipes that connect them to their parent.
+/// Not every system runs one, so this fails rather than waiting for it to appear.
+pub(crate) fn loader_server() -> io::Result<Connection> {
+    static LOADER_CONNECTION: AtomicU32 = AtomicU32::new(0);
+    let cid = LOADER_CONNECTION.load(Ordering::Relaxed);
+    if cid != 0 {
+        return Ok(cid.into());
+    }
+
+    let cid = try_connect("_Process Loader_")
+        .ok_or(io::const_io_error!(io::ErrorKind::NotFound, &"process loader is not running"))?;
+    LOADER_CONNECTION.store(cid.into(), Ordering::Relaxed);
+    Ok(cid)
+}
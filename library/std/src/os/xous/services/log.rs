@@ -45,9 +45,19 @@ pub(crate) enum LogScalar<'a> {
     }
 }
 
+#[derive(Copy, Clone)]
 pub(crate) enum LogLend {
     StandardOutput = 1,
     StandardError = 2,
+
+    /// Name the thread whose id is passed in `offset`, so that the log server can
+    /// refer to it by name when it reports a panic. `valid` is the length of the name.
+    ThreadName = 4,
+
+    /// Report the stack of a panicking thread. The lent page holds a Senres `BktQ`
+    /// message made up of the `u64` address `_start` was loaded at, followed by a
+    /// `Vec<u64>` of return addresses with the innermost frame first.
+    Backtrace = 5,
 }
 
 impl Into<usize> for LogLend {
@@ -56,6 +66,20 @@ fn into(self) -> usize {
     }
 }
 
+pub(crate) enum LogLendMut {
+    /// Block until console input is available, then fill the lent page with up to
+    /// `valid` bytes of it. Input is delivered a line at a time where possible.
+    /// The server returns the number of bytes in `valid`, and a nonzero `offset`
+    /// once the console has been closed and no more input will arrive.
+    StandardInput = 3,
+}
+
+impl Into<usize> for LogLendMut {
+    fn into(self) -> usize {
+        self as usize
+    }
+}
+
 /// Return a `Connection` to the log server, which is used for printing messages to
 /// the console and reporting panics. If the log server has not yet started, this
 /// will block until the server is running. It is safe to call this multiple times,
@@ -1,5 +1,10 @@
+#[cfg(test)]
+mod tests;
+
+use crate::net::{Ipv4Addr, Ipv6Addr};
 use crate::os::xous::ffi::Connection;
 use crate::os::xous::services::connect;
+use crate::time::Duration;
 use core::sync::atomic::{AtomicU32, Ordering};
 
 pub(crate) enum NetBlockingScalar {
@@ -12,6 +17,29 @@ pub(crate) enum NetBlockingScalar {
     StdTcpClose(u16 /* fd */),                 /* 34 */
     StdUdpClose(u16 /* fd */),                 /* 41 */
     StdTcpStreamShutdown(u16 /* fd */, crate::net::Shutdown /* how */), /* 46 */
+
+    // The following UDP socket options reply with an error code in the first
+    // word, which is 0 on success, followed by the current value for getters.
+    StdSetBroadcast(u16 /* fd */, bool), /* 49 */
+    StdGetBroadcast(u16 /* fd */),       /* 50 */
+    StdSetMulticastLoop(u16 /* fd */, bool, bool /* v6 */), /* 51 */
+    StdGetMulticastLoop(u16 /* fd */, bool /* v6 */), /* 52 */
+    StdSetMulticastTtl(u16 /* fd */, u32 /* ttl */), /* 53 */
+    StdGetMulticastTtl(u16 /* fd */),    /* 54 */
+    StdJoinMulticastV4(u16 /* fd */, Ipv4Addr, Ipv4Addr /* interface */), /* 55 */
+    StdLeaveMulticastV4(u16 /* fd */, Ipv4Addr, Ipv4Addr /* interface */), /* 56 */
+    StdJoinMulticastV6(u16 /* fd */, Ipv6Addr), /* 57 */
+    StdLeaveMulticastV6(u16 /* fd */, Ipv6Addr), /* 58 */
+    StdSetLinger(u16 /* fd */, Option<Duration>), /* 59 */
+    StdGetLinger(u16 /* fd */),          /* 60 */
+    StdSetKeepalive(u16 /* fd */, Option<Duration> /* interval */), /* 61 */
+    StdGetKeepalive(u16 /* fd */),       /* 62 */
+    /// Return and clear the error code of the last error that happened on the
+    /// socket outside of a call, such as the connection being reset. Replies
+    /// with the code in the second word, which is 0 if there was no error.
+    StdTakeErrorTcp(u16 /* fd */), /* 63 */
+    /// The same as `StdTakeErrorTcp`, for a UDP socket.
+    StdTakeErrorUdp(u16 /* fd */), /* 64 */
 }
 
 pub(crate) enum NetLendMut {
@@ -25,6 +53,29 @@ pub(crate) enum NetLendMut {
     StdUdpTx(u16 /* fd */),                           /* 43 */
     StdTcpListen,                                     /* 44 */
     StdTcpAccept(u16 /* fd */),                       /* 45 */
+
+    /// Wait until any of a set of sockets is ready, or until a timeout expires.
+    /// Request "PolQ": timeout in ms as `Option<u64>`, then a `Vec<(u16, u8, u8)>`
+    /// of fd, socket kind and interest. Reply "PolR": a `Vec<u8>` with the readiness
+    /// of each socket. The error code is returned in `offset`.
+    StdPoll, /* 48 */
+}
+
+pub(crate) enum NetLend {
+    /// Send data on a TCP stream without the server writing a response into the
+    /// buffer, so the caller's memory can be lent directly. The buffer may be any
+    /// number of pages. The error code is returned in `offset`, and the number of
+    /// bytes sent in `valid`. Older servers don't support this, and fail the
+    /// message instead.
+    StdTcpTxPages(u16 /* fd */), /* 47 */
+}
+
+impl Into<usize> for NetLend {
+    fn into(self) -> usize {
+        match self {
+            NetLend::StdTcpTxPages(fd) => 47 | ((fd as usize) << 16),
+        }
+    }
 }
 
 impl Into<usize> for NetLendMut {
@@ -44,6 +95,7 @@ fn into(self) -> usize {
             NetLendMut::StdUdpTx(fd) => 43 | ((fd as usize) << 16),
             NetLendMut::StdTcpListen => 44,
             NetLendMut::StdTcpAccept(fd) => 45 | ((fd as usize) << 16),
+            NetLendMut::StdPoll => 48,
         }
     }
 }
@@ -76,10 +128,77 @@ fn into(self) -> usize {
                 0,
                 0,
             ],
+            NetBlockingScalar::StdSetBroadcast(fd, enabled) => {
+                [49 | ((fd as usize) << 16), enabled as usize, 0, 0, 0]
+            }
+            NetBlockingScalar::StdGetBroadcast(fd) => [50 | ((fd as usize) << 16), 0, 0, 0, 0],
+            NetBlockingScalar::StdSetMulticastLoop(fd, enabled, v6) => {
+                [51 | ((fd as usize) << 16), enabled as usize, v6 as usize, 0, 0]
+            }
+            NetBlockingScalar::StdGetMulticastLoop(fd, v6) => {
+                [52 | ((fd as usize) << 16), v6 as usize, 0, 0, 0]
+            }
+            NetBlockingScalar::StdSetMulticastTtl(fd, ttl) => {
+                [53 | ((fd as usize) << 16), ttl as usize, 0, 0, 0]
+            }
+            NetBlockingScalar::StdGetMulticastTtl(fd) => [54 | ((fd as usize) << 16), 0, 0, 0, 0],
+            NetBlockingScalar::StdJoinMulticastV4(fd, group, interface) => [
+                55 | ((fd as usize) << 16),
+                u32::from(group) as usize,
+                u32::from(interface) as usize,
+                0,
+                0,
+            ],
+            NetBlockingScalar::StdLeaveMulticastV4(fd, group, interface) => [
+                56 | ((fd as usize) << 16),
+                u32::from(group) as usize,
+                u32::from(interface) as usize,
+                0,
+                0,
+            ],
+            NetBlockingScalar::StdJoinMulticastV6(fd, group) => {
+                let [a, b, c, d] = ipv6_words(group);
+                [57 | ((fd as usize) << 16), a, b, c, d]
+            }
+            NetBlockingScalar::StdLeaveMulticastV6(fd, group) => {
+                let [a, b, c, d] = ipv6_words(group);
+                [58 | ((fd as usize) << 16), a, b, c, d]
+            }
+            NetBlockingScalar::StdSetLinger(fd, linger) => {
+                let (enabled, ms) = duration_words(linger);
+                [59 | ((fd as usize) << 16), enabled, ms, 0, 0]
+            }
+            NetBlockingScalar::StdGetLinger(fd) => [60 | ((fd as usize) << 16), 0, 0, 0, 0],
+            NetBlockingScalar::StdSetKeepalive(fd, interval) => {
+                let (enabled, ms) = duration_words(interval);
+                [61 | ((fd as usize) << 16), enabled, ms, 0, 0]
+            }
+            NetBlockingScalar::StdGetKeepalive(fd) => [62 | ((fd as usize) << 16), 0, 0, 0, 0],
+            NetBlockingScalar::StdTakeErrorTcp(fd) => [63 | ((fd as usize) << 16), 0, 0, 0, 0],
+            NetBlockingScalar::StdTakeErrorUdp(fd) => [64 | ((fd as usize) << 16), 0, 0, 0, 0],
         }
     }
 }
 
+/// Encode an optional duration as an "enabled" flag and a number of milliseconds.
+fn duration_words(duration: Option<Duration>) -> (usize, usize) {
+    match duration {
+        Some(duration) => (1, duration.as_millis().min(u32::MAX as u128) as usize),
+        None => (0, 0),
+    }
+}
+
+/// Split an IPv6 address into four words, most significant first.
+fn ipv6_words(addr: Ipv6Addr) -> [usize; 4] {
+    let bits = u128::from(addr);
+    [
+        (bits >> 96) as u32 as usize,
+        (bits >> 64) as u32 as usize,
+        (bits >> 32) as u32 as usize,
+        bits as u32 as usize,
+    ]
+}
+
 /// Return a `Connection` to the Network server. This server provides all
 /// OS-level networking functions.
 pub(crate) fn net_server() -> Connection {
@@ -0,0 +1,17 @@
+use super::*;
+
+#[test]
+fn ipv6_words_are_big_endian() {
+    let addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0x1234, 0x5678);
+    assert_eq!(ipv6_words(addr), [0xff02_0000, 0, 0, 0x1234_5678]);
+    assert_eq!(ipv6_words(Ipv6Addr::UNSPECIFIED), [0; 4]);
+    assert_eq!(ipv6_words(Ipv6Addr::from(u128::MAX)), [0xffff_ffff; 4]);
+}
+
+#[test]
+fn duration_words_saturate() {
+    assert_eq!(duration_words(None), (0, 0));
+    assert_eq!(duration_words(Some(Duration::ZERO)), (1, 0));
+    assert_eq!(duration_words(Some(Duration::from_micros(2500))), (1, 2));
+    assert_eq!(duration_words(Some(Duration::MAX)), (1, u32::MAX as usize));
+}
@@ -7,6 +7,16 @@
 pub(crate) enum PddbBlockingScalar {
     SeekKeyStd(u16 /* fd */, SeekFrom),
     CloseKeyStd(u16 /* fd */),
+
+    /// Set the modification time of an open key, in milliseconds since the UNIX
+    /// epoch in UTC as reported by the systime server.
+    SetKeyModifiedStd(u16 /* fd */, u64 /* mtime */),
+
+    /// Shorten or extend an open key to the given length. Extending fills with zeroes.
+    TruncateKeyStd(u16 /* fd */, u64 /* len */),
+
+    /// Commit any data cached for an open key to flash.
+    SyncKeyStd(u16 /* fd */),
 }
 
 #[repr(usize)]
@@ -14,7 +24,16 @@ pub(crate) enum PddbLendMut {
     OpenKeyStd,
     ReadKeyStd(u16 /* fd */),
     DeleteKeyStd = 35,
+    /// List the contents of a path in a `PthR` reply. The request may be followed
+    /// by the index of the first entry to return. Servers that honour it reply
+    /// with `PthX` instead, which follows the entries with the index of the next
+    /// page as an `Option<u32>`.
     ListPathStd = 37,
+
+    /// Return the type of a path in a `StaR` reply. Newer servers reply with
+    /// `StaX` instead, which follows the type with the length of the key, then
+    /// its modification and creation times as `Option<u64>` milliseconds since
+    /// the UNIX epoch in UTC, taken from the systime server.
     StatPathStd = 38,
 
     /// Create a dict
@@ -22,6 +41,41 @@ pub(crate) enum PddbLendMut {
 
     /// Remove an empty dict
     DeleteDictStd,
+
+    /// Rename a key to a new name in the same dict, replacing any key that
+    /// already has that name. The request is a `MvKQ` message containing the
+    /// old path followed by the new path.
+    RenameKeyStd,
+
+    /// Move a key into another dict within the same basis, replacing any key
+    /// that already exists there. The request is the same as for `RenameKeyStd`.
+    MoveKeyStd,
+
+    /// List the names of all open bases, in the order they are searched.
+    ListBasesStd,
+
+    /// Create a new basis protected by a password. The request is a `NuBQ`
+    /// message containing the name followed by the password.
+    CreateBasisStd,
+
+    /// Unlock an existing basis with its password and add it to the search
+    /// order. The request is an `OpBQ` message laid out like `CreateBasisStd`.
+    OpenBasisStd,
+
+    /// Lock a basis and remove it from the search order. The request is a
+    /// `ClBQ` message containing the name.
+    CloseBasisStd,
+
+    /// Return the name of the basis that an open key lives in.
+    KeyBasisStd(u16 /* fd */),
+
+    /// Create a basis that is kept in RAM and never written to flash, and add it
+    /// to the search order. Its contents are lost when the device reboots. The
+    /// request is a `NuVQ` message containing the name.
+    CreateVolatileBasisStd,
+
+    /// Return a `StaX` reply for an open key, laid out as for `StatPathStd`.
+    StatKeyStd(u16 /* fd */),
 }
 
 #[repr(usize)]
@@ -39,6 +93,15 @@ fn into(self) -> usize {
             PddbLendMut::StatPathStd => 38,
             PddbLendMut::CreateDictStd => 40,
             PddbLendMut::DeleteDictStd => 41,
+            PddbLendMut::RenameKeyStd => 43,
+            PddbLendMut::MoveKeyStd => 44,
+            PddbLendMut::ListBasesStd => 47,
+            PddbLendMut::CreateBasisStd => 48,
+            PddbLendMut::OpenBasisStd => 49,
+            PddbLendMut::CloseBasisStd => 50,
+            PddbLendMut::KeyBasisStd(fd) => 51 | ((fd as usize) << 16),
+            PddbLendMut::CreateVolatileBasisStd => 52,
+            PddbLendMut::StatKeyStd(fd) => 53 | ((fd as usize) << 16),
         }
     }
 }
@@ -69,6 +132,21 @@ fn into(self) -> usize {
                 [39 | ((fd as usize) << 16), a1, a2, a3, 0]
             }
             PddbBlockingScalar::CloseKeyStd(fd) => [34 | ((fd as usize) << 16), 0, 0, 0, 0],
+            PddbBlockingScalar::SetKeyModifiedStd(fd, mtime) => [
+                42 | ((fd as usize) << 16),
+                (mtime as usize) & 0xffff_ffff,
+                ((mtime >> 32) as usize) & 0xffff_ffff,
+                0,
+                0,
+            ],
+            PddbBlockingScalar::TruncateKeyStd(fd, len) => [
+                45 | ((fd as usize) << 16),
+                (len as usize) & 0xffff_ffff,
+                ((len >> 32) as usize) & 0xffff_ffff,
+                0,
+                0,
+            ],
+            PddbBlockingScalar::SyncKeyStd(fd) => [46 | ((fd as usize) << 16), 0, 0, 0, 0],
         }
     }
 }
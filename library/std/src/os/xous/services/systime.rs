@@ -2,13 +2,24 @@
 use core::sync::atomic::{AtomicU32, Ordering};
 
 pub(crate) enum SystimeScalar {
+    /// Set the RTC, in milliseconds since the UNIX epoch. Replies with 0 on success.
+    SetUtcTimeMs(u64),
     GetUtcTimeMs,
+    /// Reply with the local time zone's offset from UTC in milliseconds, as an `i64`.
+    GetTzOffsetMs,
+    /// Reply with 1 if the clock has been set since boot, or 0 if it hasn't.
+    IsTimeSet,
 }
 
 impl Into<[usize; 5]> for SystimeScalar {
     fn into(self) -> [usize; 5] {
         match self {
+            SystimeScalar::SetUtcTimeMs(ms) => {
+                [1, ms as u32 as usize, (ms >> 32) as u32 as usize, 0, 0]
+            }
             SystimeScalar::GetUtcTimeMs => [3, 0, 0, 0, 0],
+            SystimeScalar::GetTzOffsetMs => [6, 0, 0, 0, 0],
+            SystimeScalar::IsTimeSet => [7, 0, 0, 0, 0],
         }
     }
 }
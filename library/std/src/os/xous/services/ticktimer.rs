@@ -1,5 +1,6 @@
 use crate::os::xous::ffi::Connection;
-use core::sync::atomic::{AtomicU32, Ordering};
+use crate::time::Duration;
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
 
 pub(crate) enum TicktimerScalar {
     ElapsedMs,
@@ -10,6 +11,28 @@ pub(crate) enum TicktimerScalar {
     NotifyCondition(usize /* cookie */, usize /* count */),
     FreeMutex(usize /* cookie */),
     FreeCondition(usize /* cookie */),
+    ElapsedNs,
+    SleepNs(u64),
+    WaitForConditionNs(usize /* cookie */, u64 /* timeout (ns) */),
+}
+
+impl TicktimerScalar {
+    /// Wait for the condition `cookie` to be notified, giving up after `timeout`.
+    /// This uses the most precise timer that the ticktimer server offers.
+    pub(crate) fn wait_for_condition(cookie: usize, timeout: Option<Duration>) -> TicktimerScalar {
+        // A timeout of zero means to wait forever, so real timeouts are at least 1.
+        match timeout {
+            None => TicktimerScalar::WaitForCondition(cookie, 0),
+            Some(timeout) if ns_timer_supported() => TicktimerScalar::WaitForConditionNs(
+                cookie,
+                timeout.as_nanos().clamp(1, u64::MAX as u128) as u64,
+            ),
+            Some(timeout) => TicktimerScalar::WaitForCondition(
+                cookie,
+                timeout.as_nanos().div_ceil(1_000_000).clamp(1, usize::MAX as u128) as usize,
+            ),
+        }
+    }
 }
 
 impl Into<[usize; 5]> for TicktimerScalar {
@@ -23,6 +46,13 @@ pub(crate) enum TicktimerScalar {
             TicktimerScalar::NotifyCondition(cookie, count) => [9, cookie, count, 0, 0],
             TicktimerScalar::FreeMutex(cookie) => [10, cookie, 0, 0, 0],
             TicktimerScalar::FreeCondition(cookie) => [11, cookie, 0, 0, 0],
+            TicktimerScalar::ElapsedNs => [12, 0, 0, 0, 0],
+            TicktimerScalar::SleepNs(nsecs) => {
+                [13, nsecs as u32 as usize, (nsecs >> 32) as u32 as usize, 0, 0]
+            }
+            TicktimerScalar::WaitForConditionNs(cookie, timeout_ns) => {
+                [14, cookie, timeout_ns as u32 as usize, (timeout_ns >> 32) as u32 as usize, 0]
+            }
         }
     }
 }
@@ -40,3 +70,27 @@ pub(crate) fn ticktimer_server() -> Connection {
     TICKTIMER_SERVER_CONNECTION.store(cid.into(), Ordering::Relaxed);
     cid
 }
+
+/// Return whether the ticktimer server supports the nanosecond opcodes. Older
+/// kernels only know about milliseconds, and reply to the unknown `ElapsedNs`
+/// with either an error or a time of zero.
+pub(crate) fn ns_timer_supported() -> bool {
+    const UNKNOWN: u8 = 0;
+    const SUPPORTED: u8 = 1;
+    const UNSUPPORTED: u8 = 2;
+    static NS_TIMER_SUPPORT: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+    match NS_TIMER_SUPPORT.load(Ordering::Relaxed) {
+        SUPPORTED => return true,
+        UNSUPPORTED => return false,
+        _ => {}
+    }
+
+    let supported = crate::os::xous::ffi::blocking_scalar(
+        ticktimer_server(),
+        TicktimerScalar::ElapsedNs.into(),
+    )
+    .is_ok_and(|result| result[0] != 0 || result[1] != 0);
+    NS_TIMER_SUPPORT.store(if supported { SUPPORTED } else { UNSUPPORTED }, Ordering::Relaxed);
+    supported
+}
@@ -0,0 +1,50 @@
+//! Xous-specific thread introspection.
+
+#![stable(feature = "rust1", since = "1.0.0")]
+
+use crate::io;
+use crate::ops::Range;
+use crate::sys::thread::Thread as ThreadImp;
+
+/// Returns the kernel's id for the current thread.
+///
+/// Unlike [`ThreadId`](crate::thread::ThreadId), this is the number that the
+/// kernel and the log server use to refer to the thread, and it may be reused
+/// once the thread has exited.
+///
+/// Returns an error if the kernel could not be asked for the id.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::thread;
+///
+/// if let Ok(id) = thread::id() {
+///     println!("running on thread {id}");
+/// }
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn id() -> io::Result<usize> {
+    ThreadImp::current_id()
+}
+
+/// Returns the range of addresses that make up the stack of the current
+/// thread, not including the guard pages on either side of it.
+///
+/// Returns `None` if the bounds are not known, which is the case for the main
+/// thread unless the loader described where it put the main thread's stack.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::thread;
+///
+/// std::thread::spawn(|| {
+///     let stack = thread::stack_bounds().unwrap();
+///     println!("{} bytes of stack", stack.end - stack.start);
+/// });
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn stack_bounds() -> Option<Range<usize>> {
+    ThreadImp::stack_bounds()
+}
@@ -0,0 +1,61 @@
+//! Xous-specific access to the realtime clock.
+//!
+//! [`SystemTime::now`] reads the clock in UTC. The functions here cover the
+//! rest of what the time server offers.
+
+#![stable(feature = "rust1", since = "1.0.0")]
+
+use crate::io;
+use crate::sys::time as time_imp;
+use crate::sys_common::IntoInner;
+use crate::time::SystemTime;
+
+/// Returns the offset of the local time zone from UTC, in seconds. The offset
+/// is positive east of Greenwich.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::time::local_offset;
+///
+/// let offset = local_offset().unwrap();
+/// println!("UTC{:+}:{:02}", offset / 3600, (offset.abs() / 60) % 60);
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn local_offset() -> io::Result<i32> {
+    let offset_ms = time_imp::local_offset_ms()?;
+    i32::try_from(offset_ms / 1000)
+        .map_err(|_| io::const_io_error!(io::ErrorKind::InvalidData, &"invalid time zone offset"))
+}
+
+/// Sets the realtime clock to `time`.
+///
+/// This is meant for programs such as NTP clients that know the correct time.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::xous::time::set_system_time;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// set_system_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000)).unwrap();
+/// ```
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn set_system_time(time: SystemTime) -> io::Result<()> {
+    if time < SystemTime::UNIX_EPOCH {
+        return Err(io::const_io_error!(
+            io::ErrorKind::InvalidInput,
+            &"time is before the UNIX epoch"
+        ));
+    }
+    time_imp::set_system_time(time.into_inner())
+}
+
+/// Returns `true` if the realtime clock has been set since the device booted.
+///
+/// Until then, [`SystemTime::now`] returns a time that may be arbitrarily far
+/// from the real one.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn is_time_set() -> io::Result<bool> {
+    time_imp::is_time_set()
+}
@@ -297,6 +297,12 @@ fn default_hook(info: &PanicInfo<'_>) {
     } else if let Some(mut out) = panic_output() {
         write(&mut out);
     }
+
+    // See `sys::backtrace::report`.
+    #[cfg(target_os = "xous")]
+    if backtrace.is_some() {
+        crate::sys::backtrace::report();
+    }
 }
 
 #[cfg(not(test))]
@@ -2176,6 +2176,9 @@ pub fn to_path_buf(&self) -> PathBuf {
     /// * On Windows, a path is absolute if it has a prefix and starts with the
     /// root: `c:\windows` is absolute, while `c:temp` and `\temp` are not.
     ///
+    /// * On Xous, a path is absolute if it starts with the `:` in front of the
+    /// name of a basis, so `is_absolute` and [`has_root`] are equivalent.
+    ///
     /// # Examples
     ///
     /// ```
@@ -2193,7 +2196,9 @@ pub fn is_absolute(&self) -> bool {
             // FIXME: Allow Redox prefixes
             self.has_root() || has_redox_scheme(self.as_u8_slice())
         } else {
-            self.has_root() && (cfg!(any(unix, target_os = "wasi")) || self.prefix().is_some())
+            self.has_root()
+                && (cfg!(any(unix, target_os = "wasi", target_os = "xous"))
+                    || self.prefix().is_some())
         }
     }
 
@@ -1,4 +1,10 @@
+#[cfg(test)]
+mod tests;
+
 use crate::alloc::{GlobalAlloc, Layout, System};
+use crate::io;
+use crate::os::xous::ffi::{adjust_limit, Limits};
+use crate::sync::atomic::{AtomicUsize, Ordering::Relaxed};
 
 #[cfg(not(test))]
 #[export_name = "_ZN16__rust_internals3std3sys4xous5alloc8DLMALLOCE"]
@@ -10,6 +16,23 @@
     static mut DLMALLOC: dlmalloc::Dlmalloc;
 }
 
+/// The number of bytes that have been handed out by the allocator and not yet
+/// freed. Only modified with the allocator lock held.
+static BYTES_IN_USE: AtomicUsize = AtomicUsize::new(0);
+
+/// The largest that `BYTES_IN_USE` has ever been.
+static PEAK_BYTES_IN_USE: AtomicUsize = AtomicUsize::new(0);
+
+/// Record that `allocated` bytes were handed out and `freed` bytes were returned.
+/// Must be called with the allocator lock held.
+fn account(allocated: usize, freed: usize) {
+    let in_use = BYTES_IN_USE.load(Relaxed) + allocated - freed;
+    BYTES_IN_USE.store(in_use, Relaxed);
+    if in_use > PEAK_BYTES_IN_USE.load(Relaxed) {
+        PEAK_BYTES_IN_USE.store(in_use, Relaxed);
+    }
+}
+
 #[stable(feature = "alloc_system_type", since = "1.28.0")]
 unsafe impl GlobalAlloc for System {
     #[inline]
@@ -17,7 +40,11 @@ unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         // SAFETY: DLMALLOC access is guaranteed to be safe because the lock gives us unique and non-reentrant access.
         // Calling malloc() is safe because preconditions on this function match the trait method preconditions.
         let _lock = lock::lock();
-        unsafe { DLMALLOC.malloc(layout.size(), layout.align()) }
+        let ptr = unsafe { DLMALLOC.malloc(layout.size(), layout.align()) };
+        if !ptr.is_null() {
+            account(layout.size(), 0);
+        }
+        ptr
     }
 
     #[inline]
@@ -25,7 +52,11 @@ unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
         // SAFETY: DLMALLOC access is guaranteed to be safe because the lock gives us unique and non-reentrant access.
         // Calling calloc() is safe because preconditions on this function match the trait method preconditions.
         let _lock = lock::lock();
-        unsafe { DLMALLOC.calloc(layout.size(), layout.align()) }
+        let ptr = unsafe { DLMALLOC.calloc(layout.size(), layout.align()) };
+        if !ptr.is_null() {
+            account(layout.size(), 0);
+        }
+        ptr
     }
 
     #[inline]
@@ -33,7 +64,8 @@ unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         // SAFETY: DLMALLOC access is guaranteed to be safe because the lock gives us unique and non-reentrant access.
         // Calling free() is safe because preconditions on this function match the trait method preconditions.
         let _lock = lock::lock();
-        unsafe { DLMALLOC.free(ptr, layout.size(), layout.align()) }
+        unsafe { DLMALLOC.free(ptr, layout.size(), layout.align()) };
+        account(0, layout.size());
     }
 
     #[inline]
@@ -41,33 +73,73 @@ unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut
         // SAFETY: DLMALLOC access is guaranteed to be safe because the lock gives us unique and non-reentrant access.
         // Calling realloc() is safe because preconditions on this function match the trait method preconditions.
         let _lock = lock::lock();
-        unsafe { DLMALLOC.realloc(ptr, layout.size(), layout.align(), new_size) }
+        let new_ptr = unsafe { DLMALLOC.realloc(ptr, layout.size(), layout.align(), new_size) };
+        if !new_ptr.is_null() {
+            account(new_size, layout.size());
+        }
+        new_ptr
+    }
+}
+
+/// Return the number of bytes currently allocated, and the most that have ever
+/// been allocated at once.
+pub fn heap_usage() -> (usize, usize) {
+    let _lock = lock::lock();
+    (BYTES_IN_USE.load(Relaxed), PEAK_BYTES_IN_USE.load(Relaxed))
+}
+
+/// Query a process limit. The kernel only changes a limit if `current` matches
+/// its present value, and otherwise returns the present value, so asking to
+/// change it from 0 to 0 reads it without modifying anything.
+fn read_limit(knob: Limits) -> io::Result<usize> {
+    adjust_limit(knob, 0, 0)
+        .or(Err(io::const_io_error!(io::ErrorKind::Other, &"unable to read process limit")))
+}
+
+/// Return the number of bytes of memory that the heap occupies.
+pub fn heap_footprint() -> io::Result<usize> {
+    read_limit(Limits::HeapSize)
+}
+
+/// Return the most memory that the heap may grow to occupy.
+pub fn heap_limit() -> io::Result<usize> {
+    read_limit(Limits::HeapMaximum)
+}
+
+/// Limit the heap to occupying at most `limit` bytes.
+pub fn set_heap_limit(limit: usize) -> io::Result<()> {
+    let current = heap_limit()?;
+    let new = adjust_limit(Limits::HeapMaximum, current, limit)
+        .or(Err(io::const_io_error!(io::ErrorKind::Other, &"unable to adjust heap limit")))?;
+    if new != limit {
+        return Err(io::const_io_error!(
+            io::ErrorKind::InvalidInput,
+            &"heap limit is smaller than the current heap, or larger than the system allows",
+        ));
     }
+    Ok(())
 }
 
 mod lock {
-    use crate::sync::atomic::{
-        AtomicI32,
-        Ordering::{Acquire, Release},
-    };
+    use crate::sys::sync::Mutex;
 
-    static LOCKED: AtomicI32 = AtomicI32::new(0);
+    /// Allocations are serialized with the same lock that backs `std::sync::Mutex`,
+    /// which asks the ticktimer server to block contending threads rather than
+    /// spinning. Taking it never allocates, so it is safe to use here.
+    static LOCK: Mutex = Mutex::new();
 
     pub struct DropLock;
 
     pub fn lock() -> DropLock {
-        loop {
-            if LOCKED.swap(1, Acquire) == 0 {
-                return DropLock;
-            }
-            crate::os::xous::ffi::do_yield();
-        }
+        // SAFETY: The lock is only released by the `DropLock` that is returned here.
+        unsafe { LOCK.lock() };
+        DropLock
     }
 
     impl Drop for DropLock {
         fn drop(&mut self) {
-            let r = LOCKED.swap(0, Release);
-            debug_assert_eq!(r, 1);
+            // SAFETY: The lock was taken when this `DropLock` was created.
+            unsafe { LOCK.unlock() };
         }
     }
 }
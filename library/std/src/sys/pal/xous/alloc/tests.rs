@@ -0,0 +1,20 @@
+use super::*;
+
+#[test]
+fn heap_limits_read_back() {
+    let footprint = heap_footprint().unwrap();
+    let limit = heap_limit().unwrap();
+    assert!(footprint > 0, "the test harness has already allocated");
+    assert!(limit >= footprint);
+
+    // Setting the limit to its present value must succeed and leave it alone
+    set_heap_limit(limit).unwrap();
+    assert_eq!(heap_limit().unwrap(), limit);
+}
+
+#[test]
+fn heap_limit_below_footprint_is_rejected() {
+    let limit = heap_limit().unwrap();
+    assert!(set_heap_limit(0).is_err());
+    assert_eq!(heap_limit().unwrap(), limit);
+}
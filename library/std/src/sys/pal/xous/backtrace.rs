@@ -0,0 +1,69 @@
+//! Stack walking for `Backtrace`. The `backtrace` crate has no unwinder for
+//! Xous, so walk the stack with `unwinding` and the `.eh_frame` section the
+//! loader hands to `_start`. Symbols aren't available on the device, so the
+//! raw addresses are also sent to the log server for a host tool to resolve.
+
+use crate::ffi::c_void;
+use crate::os::xous::ffi::try_lend;
+use crate::os::xous::senres::{self, Senres, SenresMut};
+use crate::os::xous::services::{log_server, LogLend};
+
+/// The most frames that will be sent to the log server. Each one takes eight
+/// bytes, so this comfortably fits in a single page.
+const MAX_REPORTED_FRAMES: usize = 256;
+
+pub struct Frame {
+    /// The address this frame will resume at, or the current program counter
+    /// for the innermost frame.
+    pub ip: usize,
+
+    /// The start of the function that contains `ip`, if it is known.
+    pub symbol_address: usize,
+}
+
+/// Call `f` with each frame of the current thread's stack, innermost first,
+/// until it returns `false` or the stack runs out.
+pub fn trace<F: FnMut(&Frame) -> bool>(mut f: F) {
+    extern "C" fn callback<F: FnMut(&Frame) -> bool>(
+        ctx: *mut unwind::_Unwind_Context,
+        arg: *mut c_void,
+    ) -> unwind::_Unwind_Reason_Code {
+        let f = unsafe { &mut *(arg as *mut F) };
+        let frame = unsafe {
+            Frame {
+                ip: unwind::_Unwind_GetIP(ctx).addr(),
+                symbol_address: unwind::_Unwind_GetRegionStart(ctx).addr(),
+            }
+        };
+        if frame.ip == 0 || !f(&frame) {
+            unwind::_URC_NORMAL_STOP
+        } else {
+            unwind::_URC_NO_REASON
+        }
+    }
+
+    unsafe { unwind::_Unwind_Backtrace(callback::<F>, (&mut f as *mut F).cast()) };
+}
+
+/// Send the current thread's stack to the log server along with the address
+/// the program was loaded at. This runs while panicking, so it doesn't allocate
+/// and gives up rather than block if the log server is busy.
+pub fn report() {
+    let mut addresses = [0u64; MAX_REPORTED_FRAMES];
+    let mut count = 0;
+    trace(|frame| {
+        addresses[count] = frame.ip as u64;
+        count += 1;
+        count < addresses.len()
+    });
+
+    let mut request = senres::Stack::<4096>::new();
+    {
+        let Some(mut writer) = request.writer(*b"BktQ") else {
+            return;
+        };
+        writer.append(super::os::load_base() as u64);
+        writer.append(&addresses[..count]);
+    }
+    try_lend(log_server(), LogLend::Backtrace.into(), request.as_slice(), 0, 0).ok();
+}
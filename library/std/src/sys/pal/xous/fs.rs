@@ -1,33 +1,53 @@
+#[cfg(test)]
+mod tests;
+
 use alloc::str::FromStr;
 
+use crate::borrow::Cow;
 use crate::ffi::OsString;
 use crate::fmt;
 use crate::hash::Hash;
 use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut, SeekFrom};
 use crate::os::xous::ffi::{blocking_scalar, lend_mut, OsStrExt};
+use crate::os::xous::senres::{self, Senres, SenresMut};
 use crate::os::xous::services::{pddb_server, PddbBlockingScalar, PddbLend, PddbLendMut};
 use crate::path::{Path, PathBuf};
 use crate::sys::time::SystemTime;
 use crate::sys::unsupported;
 
-use super::senres::{self, Senres, SenresMut};
-
 pub use crate::sys_common::fs::try_exists;
 
+/// Convert `p` to the string the PDDB expects, resolving it against the working
+/// dict if it's relative.
+fn path_to_str(p: &Path) -> io::Result<Cow<'_, str>> {
+    let path_as_str = p.as_os_str().to_str().ok_or_else(|| {
+        crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
+    })?;
+    Ok(crate::sys::os::resolve_path(path_as_str))
+}
+
 pub struct File {
     fd: u16,
+    /// Length of the key when it was opened, for servers that can't stat an open key
     len: u64,
+    readable: bool,
+    writable: bool,
 }
 
 #[derive(Clone)]
 pub struct FileAttr {
     pub(crate) kind: FileType,
     pub(crate) len: u64,
+    pub(crate) modified: Option<SystemTime>,
+    pub(crate) created: Option<SystemTime>,
 }
 
 pub struct ReadDir {
     root: PathBuf,
-    entries: Vec<DirEntry>,
+    path: String,
+    entries: crate::vec::IntoIter<DirEntry>,
+    /// Index of the first entry on the next page of results, if there are any
+    next_page: Option<u32>,
 }
 
 pub struct DirEntry {
@@ -39,6 +59,8 @@ pub struct DirEntry {
 
 #[derive(Clone, Debug)]
 pub struct OpenOptions {
+    read: bool,
+    write: bool,
     create_file: bool,
     append: bool,
     truncate: bool,
@@ -46,7 +68,9 @@ pub struct OpenOptions {
 }
 
 #[derive(Copy, Clone, Debug, Default)]
-pub struct FileTimes {}
+pub struct FileTimes {
+    modified: Option<SystemTime>,
+}
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct FilePermissions {}
@@ -79,18 +103,16 @@ pub fn file_type(&self) -> FileType {
     }
 
     pub fn modified(&self) -> io::Result<SystemTime> {
-        // println!("rust: FileAttr::copy()");
-        unsupported()
+        self.modified.map_or_else(unsupported, Ok)
     }
 
     pub fn accessed(&self) -> io::Result<SystemTime> {
-        // println!("rust: FileAttr::accessed()");
-        unsupported()
+        // The PDDB doesn't record reads, so the last access is the last write
+        self.modified()
     }
 
     pub fn created(&self) -> io::Result<SystemTime> {
-        // println!("rust: FileAttr::created()");
-        unsupported()
+        self.created.map_or_else(unsupported, Ok)
     }
 }
 
@@ -103,8 +125,11 @@ pub fn set_readonly(&mut self, _readonly: bool) {}
 }
 
 impl FileTimes {
+    // Access times are not stored, see `FileAttr::accessed()`
     pub fn set_accessed(&mut self, _t: SystemTime) {}
-    pub fn set_modified(&mut self, _t: SystemTime) {}
+    pub fn set_modified(&mut self, t: SystemTime) {
+        self.modified = Some(t);
+    }
 }
 
 impl FileType {
@@ -139,7 +164,19 @@ impl Iterator for ReadDir {
     type Item = io::Result<DirEntry>;
 
     fn next(&mut self) -> Option<io::Result<DirEntry>> {
-        self.entries.pop().map(|v| Ok(v))
+        loop {
+            if let Some(entry) = self.entries.next() {
+                return Some(Ok(entry));
+            }
+            let start = self.next_page.take()?;
+            match list_path(&self.path, start) {
+                Ok((entries, next_page)) => {
+                    self.entries = entries.into_iter();
+                    self.next_page = next_page;
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
     }
 }
 
@@ -166,7 +203,8 @@ pub fn metadata(&self) -> io::Result<FileAttr> {
                 crate::io::ErrorKind::NotFound,
                 "File or directory does not exist, or is corrupted",
             )),
-            _ => Ok(FileAttr { kind: self.kind, len: 0 }),
+            // The listing only includes names and types, so look up the rest
+            _ => stat_str(&self.path),
         }
     }
 
@@ -177,11 +215,22 @@ pub fn file_type(&self) -> io::Result<FileType> {
 
 impl OpenOptions {
     pub fn new() -> OpenOptions {
-        OpenOptions { create_file: false, truncate: false, append: false, create_new: false }
+        OpenOptions {
+            read: false,
+            write: false,
+            create_file: false,
+            truncate: false,
+            append: false,
+            create_new: false,
+        }
     }
 
-    pub fn read(&mut self, _read: bool) {}
-    pub fn write(&mut self, _write: bool) {}
+    pub fn read(&mut self, read: bool) {
+        self.read = read;
+    }
+    pub fn write(&mut self, write: bool) {
+        self.write = write;
+    }
     pub fn append(&mut self, append: bool) {
         self.append = append;
     }
@@ -194,14 +243,42 @@ pub fn create(&mut self, create: bool) {
     pub fn create_new(&mut self, create_new: bool) {
         self.create_new = create_new;
     }
+
+    fn writable(&self) -> bool {
+        self.write || self.append
+    }
+
+    /// Reject combinations of options that can't be satisfied, using the same
+    /// rules as other platforms.
+    fn validate(&self) -> io::Result<()> {
+        if !self.read && !self.writable() {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                "file must be opened for reading, writing, or appending",
+            ));
+        }
+        if (self.create_file || self.create_new || self.truncate) && !self.writable() {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                "creating or truncating a file requires write or append access",
+            ));
+        }
+        if self.truncate && self.append && !self.create_new {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                "a file cannot be truncated and appended to at the same time",
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl File {
     pub fn open(path: &Path, opts: &OpenOptions) -> io::Result<File> {
+        opts.validate()?;
         let mut request = senres::Stack::<4096>::new();
-        let path_as_str = path.as_os_str().to_str().ok_or_else(|| {
-            crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
-        })?;
+        let resolved = path_to_str(path)?;
+        let path_as_str: &str = &resolved;
 
         {
             let mut writer = request.writer(*b"KyOQ").ok_or_else(|| {
@@ -219,6 +296,8 @@ pub fn open(path: &Path, opts: &OpenOptions) -> io::Result<File> {
             writer.append(opts.truncate);
             writer.append(0u64); // alloc_hint
             writer.append::<Option<[u32; 4]>>(None); // callback SID
+            writer.append(opts.read);
+            writer.append(opts.writable());
         }
 
         // Make the actual call
@@ -227,11 +306,15 @@ pub fn open(path: &Path, opts: &OpenOptions) -> io::Result<File> {
                 Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
             })?;
 
+        // The server checks for an existing key and creates the new one in a
+        // single step, so `create_new` can't race with another process.
         if err != 0 {
-            return Err(crate::io::Error::new(
-                crate::io::ErrorKind::Other,
-                "error occurred when opening file",
-            ));
+            let (kind, message) = match err {
+                1 => (crate::io::ErrorKind::NotFound, "key does not exist"),
+                2 => (crate::io::ErrorKind::AlreadyExists, "key already exists"),
+                _ => (crate::io::ErrorKind::Other, "error occurred when opening file"),
+            };
+            return Err(crate::io::Error::new(kind, message));
         }
 
         let reader = request.reader(*b"KyOR").ok_or_else(|| {
@@ -246,29 +329,111 @@ pub fn open(path: &Path, opts: &OpenOptions) -> io::Result<File> {
             Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
         })?;
 
-        Ok(File { len, fd })
+        Ok(File { fd, len, readable: opts.read, writable: opts.writable() })
+    }
+
+    /// Return the name of the basis this file was found in.
+    pub fn basis(&self) -> io::Result<String> {
+        let mut request = senres::Stack::<4096>::new();
+        let (err, _) = request
+            .lend_mut(pddb_server(), PddbLendMut::KeyBasisStd(self.fd).into())
+            .or_else(|_| {
+                Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
+            })?;
+        if err != 0 {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::Other,
+                "error during operation",
+            ));
+        }
+
+        let reader = request.reader(*b"KyBR").ok_or_else(|| {
+            crate::io::Error::new(crate::io::ErrorKind::Other, "invalid response from server")
+        })?;
+        reader.try_get_from::<String>().or_else(|_| {
+            Err(crate::io::Error::new(crate::io::ErrorKind::Other, "invalid response from server"))
+        })
+    }
+
+    fn check_readable(&self) -> io::Result<()> {
+        if !self.readable {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::PermissionDenied,
+                "file was not opened for reading",
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_writable(&self) -> io::Result<()> {
+        if !self.writable {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::PermissionDenied,
+                "file was not opened for writing",
+            ));
+        }
+        Ok(())
     }
 
     pub fn file_attr(&self) -> io::Result<FileAttr> {
-        Ok(FileAttr { kind: FileType::Key, len: self.len })
+        // Ask about the open key rather than its path, which may have been
+        // renamed or removed since it was opened.
+        let mut request = senres::Stack::<4096>::new();
+        let (err, _) = request
+            .lend_mut(pddb_server(), PddbLendMut::StatKeyStd(self.fd).into())
+            .or_else(|_| {
+                Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
+            })?;
+        if err == 0 {
+            if let Some(attr) = read_extended_stat(&request) {
+                return attr;
+            }
+        }
+
+        // Older servers can't do this, so fall back to what was known at open time
+        Ok(FileAttr { kind: FileType::Key, len: self.len, modified: None, created: None })
     }
 
     pub fn fsync(&self) -> io::Result<()> {
-        // println!("rust: File::fsync()");
-        unsupported()
+        let result = blocking_scalar(pddb_server(), PddbBlockingScalar::SyncKeyStd(self.fd).into())
+            .map_err(|_| {
+                crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database")
+            })?;
+        if result[0] != 0 {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::Other,
+                "unable to commit file to flash",
+            ));
+        }
+        Ok(())
     }
 
     pub fn datasync(&self) -> io::Result<()> {
-        // println!("rust: File::datasync()");
-        unsupported()
+        // Metadata is stored alongside the data, so both are always synced together
+        self.fsync()
     }
 
-    pub fn truncate(&self, _size: u64) -> io::Result<()> {
-        // println!("rust: File::truncate()");
-        unsupported()
+    pub fn truncate(&self, size: u64) -> io::Result<()> {
+        self.check_writable()?;
+        let result = blocking_scalar(
+            pddb_server(),
+            PddbBlockingScalar::TruncateKeyStd(self.fd, size).into(),
+        )
+        .map_err(|_| {
+            crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database")
+        })?;
+        match result[0] {
+            0 => Ok(()),
+            1 => Err(crate::io::Error::new(
+                crate::io::ErrorKind::StorageFull,
+                "not enough space to extend file",
+            )),
+            _ => Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to truncate file")),
+        }
     }
 
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check_readable()?;
         #[repr(C, align(4096))]
         struct ReadBuffer {
             data: [u8; 4096],
@@ -314,6 +479,7 @@ pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
     }
 
     pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.check_writable()?;
         #[repr(C, align(4096))]
         struct ReadBuffer {
             data: [u8; 4096],
@@ -361,8 +527,9 @@ pub fn is_write_vectored(&self) -> bool {
     }
 
     pub fn flush(&self) -> io::Result<()> {
-        // println!("rust: File::flush()");
-        unsupported()
+        // Writes are not buffered on this side, but the PDDB caches them before
+        // committing to flash.
+        self.fsync()
     }
 
     pub fn seek(&self, pos: SeekFrom) -> io::Result<u64> {
@@ -383,8 +550,25 @@ pub fn set_permissions(&self, _perm: FilePermissions) -> io::Result<()> {
         unsupported()
     }
 
-    pub fn set_times(&self, _times: FileTimes) -> io::Result<()> {
-        unsupported()
+    pub fn set_times(&self, times: FileTimes) -> io::Result<()> {
+        self.check_writable()?;
+        let Some(modified) = times.modified else {
+            return Ok(());
+        };
+        let result = blocking_scalar(
+            pddb_server(),
+            PddbBlockingScalar::SetKeyModifiedStd(self.fd, modified.as_unix_ms()).into(),
+        )
+        .map_err(|_| {
+            crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database")
+        })?;
+        if result[0] != 0 {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::Other,
+                "unable to set file times",
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -400,11 +584,10 @@ pub fn new() -> DirBuilder {
     }
 
     pub fn mkdir(&self, p: &Path) -> io::Result<()> {
-        let path_as_str = p.as_os_str().to_str().ok_or_else(|| {
-            crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
-        })?;
+        let path = path_to_str(p)?;
+        let path_as_str: &str = &path;
 
-        let mut request = super::senres::Stack::<4096>::new();
+        let mut request = senres::Stack::<4096>::new();
 
         let mut writer = request.writer(*b"NuDQ").ok_or_else(|| {
             crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
@@ -426,9 +609,8 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 }
 
 pub fn readdir(p: &Path) -> io::Result<ReadDir> {
-    let path_as_str = p.as_os_str().to_str().ok_or_else(|| {
-        crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
-    })?;
+    let path = path_to_str(p)?;
+    let path_as_str: &str = &path;
     let (_basis, _dict) = match crate::sys::path::split_basis_and_dict(path_as_str, || None) {
         Ok(s) => s,
         Err(_) => {
@@ -436,7 +618,20 @@ pub fn readdir(p: &Path) -> io::Result<ReadDir> {
         }
     };
 
-    let mut request = super::senres::Stack::<4096>::new();
+    let (entries, next_page) = list_path(path_as_str, 0)?;
+    Ok(ReadDir {
+        root: p.to_owned(),
+        path: path_as_str.to_owned(),
+        entries: entries.into_iter(),
+        next_page,
+    })
+}
+
+/// Fetch one page of the listing of `path_as_str`, beginning with entry number
+/// `start`. Entries are returned in the order the PDDB stores them, along with
+/// the index of the next page if the listing didn't fit in this one.
+fn list_path(path_as_str: &str, start: u32) -> io::Result<(Vec<DirEntry>, Option<u32>)> {
+    let mut request = senres::Stack::<4096>::new();
 
     // Write the request to the call
     {
@@ -444,6 +639,7 @@ pub fn readdir(p: &Path) -> io::Result<ReadDir> {
             crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
         })?;
         writer.append(path_as_str);
+        writer.append(start);
     }
 
     // Make the actual call
@@ -451,15 +647,20 @@ pub fn readdir(p: &Path) -> io::Result<ReadDir> {
         Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
     })?;
 
-    // Read the data back
-    let reader = request.reader(*b"PthR").ok_or_else(|| {
+    // Read the data back. Servers that support paging reply with `PthX`, while
+    // older ones reply with `PthR` and always return the whole listing.
+    let paged = request.reader(*b"PthX");
+    let paging = paged.is_some();
+    let reader = paged.or_else(|| request.reader(*b"PthR")).ok_or_else(|| {
         crate::io::Error::new(crate::io::ErrorKind::Other, "invalid response from server")
     })?;
+    let invalid_response =
+        || crate::io::Error::new(crate::io::ErrorKind::Other, "invalid response from server");
 
     let mut entries = vec![];
-    let count = reader.try_get_from::<u32>().unwrap() as usize;
+    let count = reader.try_get_from::<u32>().or_else(|_| Err(invalid_response()))?;
     for _ in 0..count {
-        let name = reader.try_get_ref_from().unwrap();
+        let name: &str = reader.try_get_ref_from().or_else(|_| Err(invalid_response()))?;
         let kind = match reader.try_get_from::<u8>() {
             Ok(0) => FileType::Basis,
             Ok(1) => FileType::Dict,
@@ -481,14 +682,21 @@ pub fn readdir(p: &Path) -> io::Result<ReadDir> {
         });
     }
 
-    return Ok(ReadDir { entries, root: p.to_owned() });
+    // Only keep going while each page makes progress, so that a server that
+    // keeps returning the same page can't make `ReadDir` loop forever.
+    let next_page = if paging {
+        reader.try_get_from::<Option<u32>>().or_else(|_| Err(invalid_response()))?
+    } else {
+        None
+    };
+    let next_page = next_page.filter(|&next| count != 0 && next > start);
+    Ok((entries, next_page))
 }
 
 pub fn unlink(p: &Path) -> io::Result<()> {
-    let path_as_str = p.as_os_str().to_str().ok_or_else(|| {
-        crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
-    })?;
-    let mut request = super::senres::Stack::<4096>::new();
+    let path = path_to_str(p)?;
+    let path_as_str: &str = &path;
+    let mut request = senres::Stack::<4096>::new();
     {
         let mut writer = request.writer(*b"RmKQ").ok_or_else(|| {
             crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
@@ -508,9 +716,70 @@ pub fn unlink(p: &Path) -> io::Result<()> {
     Ok(())
 }
 
-pub fn rename(_old: &Path, _new: &Path) -> io::Result<()> {
-    // println!("rust: rename()");
-    unsupported()
+/// Split a path to a key into its basis and the dict containing the key.
+fn split_key_path(path: &str) -> io::Result<(Option<&str>, &str)> {
+    let (basis, rest) = crate::sys::path::split_basis_and_dict(path, || None).map_err(|_| {
+        crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
+    })?;
+    let (dict, _key) =
+        rest.and_then(|rest| rest.rsplit_once(crate::path::MAIN_SEPARATOR)).ok_or_else(|| {
+            crate::io::Error::new(
+                crate::io::ErrorKind::InvalidFilename,
+                "path is not a key in a dict",
+            )
+        })?;
+    Ok((basis, dict))
+}
+
+pub fn rename(old: &Path, new: &Path) -> io::Result<()> {
+    let old_resolved = path_to_str(old)?;
+    let old_as_str: &str = &old_resolved;
+    let new_resolved = path_to_str(new)?;
+    let new_as_str: &str = &new_resolved;
+    let (old_basis, old_dict) = split_key_path(old_as_str)?;
+    let (new_basis, new_dict) = split_key_path(new_as_str)?;
+
+    // Keys are encrypted with the key of the basis they live in, so they can't
+    // be moved to another basis without copying. The server also checks this,
+    // since a path without a basis may resolve to any of them.
+    if old_basis.is_some() && new_basis.is_some() && old_basis != new_basis {
+        return Err(crate::io::Error::new(
+            crate::io::ErrorKind::CrossesDevices,
+            "cannot rename a key into a different basis",
+        ));
+    }
+    let opcode = if old_basis == new_basis && old_dict == new_dict {
+        PddbLendMut::RenameKeyStd
+    } else {
+        PddbLendMut::MoveKeyStd
+    };
+
+    let mut request = senres::Stack::<4096>::new();
+    {
+        let mut writer = request.writer(*b"MvKQ").ok_or_else(|| {
+            crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
+        })?;
+        writer.append(old_as_str);
+        writer.append(new_as_str);
+    }
+
+    // Make the actual call
+    let (err, _) = request.lend_mut(pddb_server(), opcode.into()).or_else(|_| {
+        Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
+    })?;
+
+    match err {
+        0 => Ok(()),
+        1 => Err(crate::io::Error::new(crate::io::ErrorKind::NotFound, "key does not exist")),
+        2 => Err(crate::io::Error::new(
+            crate::io::ErrorKind::CrossesDevices,
+            "cannot rename a key into a different basis",
+        )),
+        3 => {
+            Err(crate::io::Error::new(crate::io::ErrorKind::IsADirectory, "destination is a dict"))
+        }
+        _ => Err(crate::io::Error::new(crate::io::ErrorKind::Other, "error during operation")),
+    }
 }
 
 pub fn set_perm(_p: &Path, _perm: FilePermissions) -> io::Result<()> {
@@ -519,11 +788,10 @@ pub fn set_perm(_p: &Path, _perm: FilePermissions) -> io::Result<()> {
 }
 
 pub fn rmdir(p: &Path) -> io::Result<()> {
-    let path_as_str = p.as_os_str().to_str().ok_or_else(|| {
-        crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
-    })?;
+    let path = path_to_str(p)?;
+    let path_as_str: &str = &path;
 
-    let mut request = super::senres::Stack::<4096>::new();
+    let mut request = senres::Stack::<4096>::new();
 
     let mut writer = request.writer(*b"RmDQ").ok_or_else(|| {
         crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
@@ -555,6 +823,7 @@ pub fn remove_dir_all(path: &Path) -> io::Result<()> {
 }
 
 pub fn readlink(p: &Path) -> io::Result<PathBuf> {
+    // The PDDB has no symbolic links, so anything that exists isn't one.
     stat(p)?;
     Err(io::const_io_error!(io::ErrorKind::InvalidInput, "not a symbolic link"))
 }
@@ -570,10 +839,11 @@ pub fn link(_src: &Path, _dst: &Path) -> io::Result<()> {
 }
 
 pub fn stat(p: &Path) -> io::Result<FileAttr> {
-    let path_as_str = p.as_os_str().to_str().ok_or_else(|| {
-        crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
-    })?;
-    let mut request = super::senres::Stack::<4096>::new();
+    stat_str(&path_to_str(p)?)
+}
+
+fn stat_str(path_as_str: &str) -> io::Result<FileAttr> {
+    let mut request = senres::Stack::<4096>::new();
 
     // Write the request to the call
     {
@@ -588,9 +858,44 @@ pub fn stat(p: &Path) -> io::Result<FileAttr> {
         Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
     })?;
 
-    // Read the data back
-    let reader = request.reader(*b"StaR").expect("unable to get reader");
-    let kind = match reader.try_get_from::<u8>() {
+    // Read the data back. Newer servers reply with the extended layout, while
+    // older ones reuse the request page and only fill in the type, so nothing
+    // after the type can be trusted in a `StaR` reply.
+    if let Some(attr) = read_extended_stat(&request) {
+        return attr;
+    }
+    let reader = request.reader(*b"StaR").ok_or_else(|| {
+        crate::io::Error::new(crate::io::ErrorKind::Other, "invalid response from server")
+    })?;
+    stat_result(reader.try_get_from::<u8>(), 0, None, None)
+}
+
+/// Parse a `StaX` reply, which follows the type with the length of the key and
+/// its modification and creation times. Returns `None` if the server replied
+/// with something else.
+fn read_extended_stat(request: &senres::Stack<4096>) -> Option<io::Result<FileAttr>> {
+    let reader = request.reader(*b"StaX")?;
+    let kind = reader.try_get_from::<u8>();
+    let (Ok(len), Ok(modified), Ok(created)) = (
+        reader.try_get_from::<u64>(),
+        reader.try_get_from::<Option<u64>>(),
+        reader.try_get_from::<Option<u64>>(),
+    ) else {
+        return Some(Err(crate::io::Error::new(
+            crate::io::ErrorKind::Other,
+            "invalid response from server",
+        )));
+    };
+    Some(stat_result(kind, len, modified, created))
+}
+
+fn stat_result(
+    kind: Result<u8, ()>,
+    len: u64,
+    modified: Option<u64>,
+    created: Option<u64>,
+) -> io::Result<FileAttr> {
+    let kind = match kind {
         Ok(0) => FileType::Basis,
         Ok(1) => FileType::Dict,
         Ok(2) => FileType::Key,
@@ -605,7 +910,12 @@ pub fn stat(p: &Path) -> io::Result<FileAttr> {
             crate::io::ErrorKind::NotFound,
             "File or directory does not exist, or is corrupted",
         )),
-        _ => Ok(FileAttr { kind, len: 0 }),
+        _ => Ok(FileAttr {
+            kind,
+            len,
+            modified: modified.map(SystemTime::from_unix_ms),
+            created: created.map(SystemTime::from_unix_ms),
+        }),
     }
 }
 
@@ -614,9 +924,58 @@ pub fn lstat(p: &Path) -> io::Result<FileAttr> {
     stat(p)
 }
 
-pub fn canonicalize(_p: &Path) -> io::Result<PathBuf> {
-    // println!("rust: canonicalize()");
-    unsupported()
+/// Collapse runs of separators and drop any trailing ones, keeping the empty
+/// basis name of a leading `::`.
+fn normalize_separators(path: &str) -> String {
+    let sep = crate::path::MAIN_SEPARATOR;
+    let (mut normalized, rest) = if let Some(rest) = path.strip_prefix("::") {
+        (String::from("::"), rest)
+    } else if let Some(rest) = path.strip_prefix(sep) {
+        (String::from(":"), rest)
+    } else {
+        (String::new(), path)
+    };
+    for (i, component) in rest.split(sep).filter(|c| !c.is_empty()).enumerate() {
+        if i > 0 {
+            normalized.push(sep);
+        }
+        normalized.push_str(component);
+    }
+    normalized
+}
+
+pub fn canonicalize(p: &Path) -> io::Result<PathBuf> {
+    let normalized = normalize_separators(&path_to_str(p)?);
+    if normalized == ":" {
+        return Ok(PathBuf::from(normalized));
+    }
+    let (basis, dict) =
+        crate::sys::path::split_basis_and_dict(&normalized, || None).map_err(|_| {
+            crate::io::Error::new(crate::io::ErrorKind::InvalidFilename, "invalid path")
+        })?;
+    let dict = dict.filter(|dict| !dict.is_empty());
+    let in_basis = |basis: &str| match dict {
+        Some(dict) => format!(":{basis}:{dict}"),
+        None => format!(":{basis}"),
+    };
+
+    if let Some(basis) = basis {
+        let path = in_basis(basis);
+        stat_str(&path)?;
+        return Ok(PathBuf::from(path));
+    }
+
+    // Without a basis, the path refers to the first open basis that has it.
+    for basis in list_bases()? {
+        let path = in_basis(&basis);
+        if stat_str(&path).is_ok() {
+            return Ok(PathBuf::from(path));
+        }
+    }
+    Err(crate::io::Error::new(
+        crate::io::ErrorKind::NotFound,
+        "File or directory does not exist, or is corrupted",
+    ))
 }
 
 pub fn copy(from: &Path, to: &Path) -> io::Result<u64> {
@@ -627,3 +986,75 @@ pub fn copy(from: &Path, to: &Path) -> io::Result<u64> {
 
     io::copy(&mut reader, &mut writer)
 }
+
+/// Return the names of all open bases, in the order they are searched when a
+/// path doesn't name a basis.
+pub fn list_bases() -> io::Result<Vec<String>> {
+    let mut request = senres::Stack::<4096>::new();
+    request.lend_mut(pddb_server(), PddbLendMut::ListBasesStd.into()).or_else(|_| {
+        Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
+    })?;
+
+    let reader = request.reader(*b"LsBR").ok_or_else(|| {
+        crate::io::Error::new(crate::io::ErrorKind::Other, "invalid response from server")
+    })?;
+    reader.try_get_from::<Vec<String>>().or_else(|_| {
+        Err(crate::io::Error::new(crate::io::ErrorKind::Other, "invalid response from server"))
+    })
+}
+
+pub fn create_basis(name: &str, password: &str) -> io::Result<()> {
+    basis_request(PddbLendMut::CreateBasisStd, *b"NuBQ", name, Some(password))
+}
+
+pub fn create_volatile_basis(name: &str) -> io::Result<()> {
+    basis_request(PddbLendMut::CreateVolatileBasisStd, *b"NuVQ", name, None)
+}
+
+pub fn open_basis(name: &str, password: &str) -> io::Result<()> {
+    basis_request(PddbLendMut::OpenBasisStd, *b"OpBQ", name, Some(password))
+}
+
+pub fn close_basis(name: &str) -> io::Result<()> {
+    basis_request(PddbLendMut::CloseBasisStd, *b"ClBQ", name, None)
+}
+
+fn basis_request(
+    opcode: PddbLendMut,
+    fourcc: [u8; 4],
+    name: &str,
+    password: Option<&str>,
+) -> io::Result<()> {
+    let mut request = senres::Stack::<4096>::new();
+    {
+        let mut writer = request.writer(fourcc).ok_or_else(|| {
+            crate::io::Error::new(crate::io::ErrorKind::InvalidInput, "unable to create request")
+        })?;
+        writer.append(name);
+        if let Some(password) = password {
+            writer.append(password);
+        }
+    }
+
+    // Make the actual call
+    let result = request.lend_mut(pddb_server(), opcode.into());
+
+    // Don't leave the password lying around on the stack
+    for byte in request.as_mut_slice() {
+        // SAFETY: `byte` is a valid reference into the request buffer.
+        unsafe { crate::ptr::write_volatile(byte, 0) };
+    }
+
+    let (err, _) = result.or_else(|_| {
+        Err(crate::io::Error::new(crate::io::ErrorKind::Other, "unable to query database"))
+    })?;
+    let (kind, message) = match err {
+        0 => return Ok(()),
+        1 => (crate::io::ErrorKind::NotFound, "basis does not exist"),
+        2 => (crate::io::ErrorKind::PermissionDenied, "incorrect password"),
+        3 => (crate::io::ErrorKind::AlreadyExists, "basis already exists"),
+        4 => (crate::io::ErrorKind::ResourceBusy, "basis is in use"),
+        _ => (crate::io::ErrorKind::Other, "error during operation"),
+    };
+    Err(crate::io::Error::new(kind, message))
+}
@@ -0,0 +1,28 @@
+use super::*;
+
+#[test]
+fn split_key_path_finds_dict() {
+    assert_eq!(split_key_path(":Basis:dict:key").unwrap(), (Some("Basis"), "dict"));
+    assert_eq!(split_key_path(":Basis:outer:inner:key").unwrap(), (Some("Basis"), "outer:inner"));
+    assert_eq!(split_key_path("dict:key").unwrap(), (None, "dict"));
+    // An empty basis name searches every basis
+    assert_eq!(split_key_path("::dict:key").unwrap(), (None, "dict"));
+}
+
+#[test]
+fn split_key_path_needs_a_dict() {
+    assert!(split_key_path("key").is_err());
+    assert!(split_key_path(":Basis").is_err());
+    assert!(split_key_path(":Basis:").is_err());
+    assert!(split_key_path("dict:key:").is_err());
+}
+
+#[test]
+fn normalize_separators_collapses_runs() {
+    assert_eq!(normalize_separators("dict::key:"), "dict:key");
+    assert_eq!(normalize_separators(":Basis::dict:"), ":Basis:dict");
+    assert_eq!(normalize_separators(":::dict"), "::dict");
+    assert_eq!(normalize_separators("::"), "::");
+    assert_eq!(normalize_separators(":"), ":");
+    assert_eq!(normalize_separators(""), "");
+}
@@ -2,6 +2,7 @@
 
 pub mod alloc;
 pub mod args;
+pub mod backtrace;
 #[path = "../unsupported/env.rs"]
 pub mod env;
 pub mod fs;
@@ -9,10 +10,9 @@
 pub mod io;
 pub mod net;
 pub mod os;
-#[path = "../unsupported/pipe.rs"]
 pub mod pipe;
-#[path = "../unsupported/process.rs"]
 pub mod process;
+pub mod stack_overflow;
 pub mod stdio;
 pub mod thread;
 pub mod thread_local_key;
@@ -22,4 +22,9 @@
 mod common;
 pub use common::*;
 
-mod senres;
+// SAFETY: must be called only once during runtime cleanup.
+pub unsafe fn cleanup() {
+    // Console output is buffered until a newline is seen, so make sure any
+    // trailing partial line gets printed before the process exits.
+    stdio::flush_all();
+}
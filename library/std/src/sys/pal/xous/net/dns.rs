@@ -1,96 +1,115 @@
 use crate::io;
-use crate::net::{Ipv4Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use crate::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use crate::os::xous::ffi::lend_mut;
-use crate::os::xous::services::{dns_server, DnsLendMut};
+use crate::os::xous::services::{dns_server, DnsLendMut, DnsQueryType};
+use crate::time::Duration;
 use core::convert::{TryFrom, TryInto};
 
-pub struct DnsError {
-    #[allow(dead_code)]
-    pub code: u8,
-}
-
 #[repr(C, align(4096))]
 struct LookupHostQuery([u8; 4096]);
 
+/// Convert an error code from the DNS resolver into an `io::Error`.
+fn dns_error(code: u8) -> io::Error {
+    // These need to be synchronized with what's in dns/src/api.rs
+    match code {
+        // FormatError
+        1 => io::const_io_error!(io::ErrorKind::InvalidInput, &"Malformed DNS query"),
+        // ServerFailure
+        2 => io::const_io_error!(io::ErrorKind::Other, &"DNS server failure"),
+        // NameError, or NXDOMAIN
+        3 => io::const_io_error!(io::ErrorKind::NotFound, &"Host not found"),
+        // NotImplemented
+        4 => io::const_io_error!(io::ErrorKind::Unsupported, &"DNS query not supported by server"),
+        // Refused
+        5 => io::const_io_error!(io::ErrorKind::ConnectionRefused, &"DNS query refused"),
+        // NetworkError
+        7 => io::const_io_error!(io::ErrorKind::NetworkDown, &"Network is not available"),
+        // TimedOut
+        8 => io::const_io_error!(io::ErrorKind::TimedOut, &"DNS query timed out"),
+        // NoServer
+        9 => io::const_io_error!(io::ErrorKind::NetworkUnreachable, &"No DNS server is configured"),
+        _ => io::const_io_error!(io::ErrorKind::Other, &"DNS failure"),
+    }
+}
+
 pub struct LookupHost {
     data: LookupHostQuery,
     port: u16,
     offset: usize,
     count: usize,
+    with_ttl: bool,
 }
 
 impl LookupHost {
     pub fn port(&self) -> u16 {
         self.port
     }
-}
 
-impl Iterator for LookupHost {
-    type Item = SocketAddr;
-    fn next(&mut self) -> Option<SocketAddr> {
-        if self.offset >= self.data.0.len() {
+    /// Parse the next record, along with its TTL in seconds if the query asked for it.
+    fn next_record(&mut self) -> Option<(IpAddr, u32)> {
+        if self.count == 0 {
             return None;
         }
-        match self.data.0.get(self.offset) {
-            Some(&4) => {
-                self.offset += 1;
-                if self.offset + 4 > self.data.0.len() {
-                    return None;
-                }
-                let result = Some(SocketAddr::V4(SocketAddrV4::new(
-                    Ipv4Addr::new(
-                        self.data.0[self.offset],
-                        self.data.0[self.offset + 1],
-                        self.data.0[self.offset + 2],
-                        self.data.0[self.offset + 3],
-                    ),
-                    self.port,
-                )));
-                self.offset += 4;
-                result
+        let data = &self.data.0;
+        let (addr, len) = match data.get(self.offset)? {
+            4 => {
+                let octets: [u8; 4] =
+                    data.get(self.offset + 1..self.offset + 5)?.try_into().ok()?;
+                (IpAddr::V4(Ipv4Addr::from(octets)), 4)
             }
-            Some(&6) => {
-                self.offset += 1;
-                if self.offset + 16 > self.data.0.len() {
-                    return None;
-                }
-                let mut new_addr = [0u8; 16];
-                for (src, octet) in self.data.0[(self.offset + 1)..(self.offset + 16 + 1)]
-                    .iter()
-                    .zip(new_addr.iter_mut())
-                {
-                    *octet = *src;
-                }
-                let result =
-                    Some(SocketAddr::V6(SocketAddrV6::new(new_addr.into(), self.port, 0, 0)));
-                self.offset += 16;
-                result
+            6 => {
+                let octets: [u8; 16] =
+                    data.get(self.offset + 1..self.offset + 17)?.try_into().ok()?;
+                (IpAddr::V6(Ipv6Addr::from(octets)), 16)
             }
-            _ => None,
-        }
+            _ => return None,
+        };
+        let mut offset = self.offset + 1 + len;
+        let ttl = if self.with_ttl {
+            let ttl = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?);
+            offset += 4;
+            ttl
+        } else {
+            0
+        };
+        self.offset = offset;
+        self.count -= 1;
+        Some((addr, ttl))
+    }
+}
+
+impl Iterator for LookupHost {
+    type Item = SocketAddr;
+    fn next(&mut self) -> Option<SocketAddr> {
+        self.next_record().map(|(addr, _)| SocketAddr::new(addr, self.port))
     }
 }
 
-pub fn lookup(query: &str, port: u16) -> Result<LookupHost, DnsError> {
-    let mut result = LookupHost { data: LookupHostQuery([0u8; 4096]), offset: 0, count: 0, port };
+fn lookup_inner(
+    query: &str,
+    port: u16,
+    query_type: DnsQueryType,
+    with_ttl: bool,
+) -> io::Result<LookupHost> {
+    let mut result =
+        LookupHost { data: LookupHostQuery([0u8; 4096]), offset: 0, count: 0, port, with_ttl };
+
+    if query.len() > result.data.0.len() {
+        return Err(io::const_io_error!(io::ErrorKind::InvalidInput, &"Host name is too long"));
+    }
 
     // Copy the query into the message that gets sent to the DNS server
-    for (query_byte, result_byte) in query.as_bytes().iter().zip(result.data.0.iter_mut()) {
-        *result_byte = *query_byte;
-    }
+    result.data.0[..query.len()].copy_from_slice(query.as_bytes());
 
-    lend_mut(
-        dns_server(),
-        DnsLendMut::RawLookup.into(),
-        &mut result.data.0,
-        0,
-        query.as_bytes().len(),
-    )
-    .unwrap();
+    let opcode = if with_ttl { DnsLendMut::StdLookupWithTtl } else { DnsLendMut::RawLookup };
+    lend_mut(dns_server(), opcode.into(), &mut result.data.0, query_type as usize, query.len())
+        .or(Err(io::const_io_error!(
+            io::ErrorKind::NotConnected,
+            &"Unable to communicate with the DNS resolver"
+        )))?;
     if result.data.0[0] != 0 {
-        return Err(DnsError { code: result.data.0[1] });
+        return Err(dns_error(result.data.0[1]));
     }
-    assert_eq!(result.offset, 0);
     result.count = result.data.0[1] as usize;
 
     // Advance the offset to the first record
@@ -98,6 +117,42 @@ pub fn lookup(query: &str, port: u16) -> Result<LookupHost, DnsError> {
     Ok(result)
 }
 
+pub fn lookup(query: &str, port: u16) -> io::Result<LookupHost> {
+    lookup_inner(query, port, DnsQueryType::Any, false)
+}
+
+/// Older resolvers ignore the query type and return every record they find, so
+/// check that each address is of the family that was asked for.
+fn matches_query(addr: &IpAddr, query_type: DnsQueryType) -> bool {
+    match query_type {
+        DnsQueryType::Any => true,
+        DnsQueryType::V4 => addr.is_ipv4(),
+        DnsQueryType::V6 => addr.is_ipv6(),
+    }
+}
+
+/// Resolve `query` to a list of addresses of the given family.
+pub fn lookup_addresses(query: &str, query_type: DnsQueryType) -> io::Result<Vec<IpAddr>> {
+    let mut result = lookup_inner(query, 0, query_type, false)?;
+    Ok(crate::iter::from_fn(|| result.next_record())
+        .map(|(addr, _)| addr)
+        .filter(|addr| matches_query(addr, query_type))
+        .collect())
+}
+
+/// Resolve `query` to a list of addresses of the given family, along with how
+/// long each address may be cached for.
+pub fn lookup_with_ttl(
+    query: &str,
+    query_type: DnsQueryType,
+) -> io::Result<Vec<(IpAddr, Duration)>> {
+    let mut result = lookup_inner(query, 0, query_type, true)?;
+    Ok(crate::iter::from_fn(|| result.next_record())
+        .filter(|(addr, _)| matches_query(addr, query_type))
+        .map(|(addr, ttl)| (addr, Duration::from_secs(ttl.into())))
+        .collect())
+}
+
 impl TryFrom<&str> for LookupHost {
     type Error = io::Error;
 
@@ -123,6 +178,5 @@ impl TryFrom<(&str, u16)> for LookupHost {
 
     fn try_from(v: (&str, u16)) -> io::Result<LookupHost> {
         lookup(v.0, v.1)
-            .map_err(|_e| io::const_io_error!(io::ErrorKind::InvalidInput, &"DNS failure"))
     }
 }
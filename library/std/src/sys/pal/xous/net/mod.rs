@@ -1,3 +1,6 @@
+use crate::io;
+use crate::os::xous::services;
+
 mod dns;
 
 mod tcpstream;
@@ -9,6 +12,9 @@
 mod udp;
 pub use udp::*;
 
+mod poll;
+pub use poll::*;
+
 // this structure needs to be synchronized with what's in net/src/api.rs
 #[repr(C)]
 #[derive(Debug)]
@@ -18,13 +24,49 @@ enum NetError {
     SocketInUse = 2,
     // AccessDenied = 3,
     Invalid = 4,
-    // Finished = 5,
+    Finished = 5,
     LibraryError = 6,
     // AlreadyUsed = 7,
     TimedOut = 8,
     WouldBlock = 9,
 }
 
+/// Convert an error code returned by the network server into an `io::Error`.
+fn net_error(code: usize) -> io::Error {
+    match code {
+        code if code == NetError::Unaddressable as usize => {
+            io::const_io_error!(io::ErrorKind::AddrNotAvailable, &"Address is not available")
+        }
+        code if code == NetError::SocketInUse as usize => {
+            io::const_io_error!(io::ErrorKind::AddrInUse, &"Address is already in use")
+        }
+        code if code == NetError::Invalid as usize => {
+            io::const_io_error!(io::ErrorKind::InvalidInput, &"Invalid argument")
+        }
+        code if code == NetError::Finished as usize => {
+            io::const_io_error!(io::ErrorKind::ConnectionReset, &"Connection reset by peer")
+        }
+        code if code == NetError::LibraryError as usize => {
+            io::const_io_error!(io::ErrorKind::Other, &"Network library error")
+        }
+        code if code == NetError::TimedOut as usize => {
+            io::const_io_error!(io::ErrorKind::TimedOut, &"Timeout")
+        }
+        _ => io::const_io_error!(io::ErrorKind::Other, &"Unknown network error"),
+    }
+}
+
+/// Send a request for a socket option that replies with an error code in the
+/// first word, which is 0 on success, followed by the value of the option.
+fn socket_option(request: services::NetBlockingScalar) -> io::Result<[usize; 5]> {
+    let response = crate::os::xous::ffi::blocking_scalar(services::net_server(), request.into())
+        .or(Err(io::const_io_error!(io::ErrorKind::InvalidInput, &"Unexpected return value")))?;
+    match response[0] {
+        0 => Ok(response),
+        code => Err(net_error(code)),
+    }
+}
+
 #[repr(C, align(4096))]
 struct ConnectRequest {
     raw: [u8; 4096],
@@ -45,7 +87,7 @@ pub struct GetAddress {
     raw: [u8; 4096],
 }
 
-pub use dns::LookupHost;
+pub use dns::{lookup_addresses, lookup_with_ttl, LookupHost};
 
 #[allow(nonstandard_style)]
 pub mod netc {
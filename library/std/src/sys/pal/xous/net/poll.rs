@@ -0,0 +1,95 @@
+use super::*;
+use crate::io;
+use crate::os::xous::senres::{self, Senres, SenresMut};
+use crate::os::xous::services;
+use crate::time::Duration;
+
+/// The socket may be read from without blocking.
+pub const POLL_READABLE: u8 = 1 << 0;
+/// The socket may be written to without blocking.
+pub const POLL_WRITABLE: u8 = 1 << 1;
+/// A listening socket has a connection waiting to be accepted.
+pub const POLL_ACCEPT: u8 = 1 << 2;
+
+/// The most sockets that may be waited on with a single call.
+pub const MAX_POLL_SOURCES: usize = 512;
+
+// These need to be synchronized with what's in net/src/api.rs
+#[derive(Copy, Clone)]
+pub(super) enum SocketKind {
+    TcpStream = 0,
+    TcpListener = 1,
+    Udp = 2,
+}
+
+/// A socket that can be waited on with `poll()`.
+#[derive(Copy, Clone)]
+pub struct PollSource {
+    pub(super) fd: u16,
+    pub(super) kind: SocketKind,
+}
+
+/// Wait until at least one of `sources` is ready for one of the events in its
+/// interest set, or until `timeout` expires. The readiness of each source is
+/// written to `ready`, and the number of ready sources is returned.
+pub fn poll(
+    sources: &[(PollSource, u8)],
+    ready: &mut [u8],
+    timeout: Option<Duration>,
+) -> io::Result<usize> {
+    if sources.len() > MAX_POLL_SOURCES {
+        return Err(io::const_io_error!(io::ErrorKind::InvalidInput, &"Too many sockets to poll"));
+    }
+
+    let mut request = senres::Stack::<4096>::new();
+    {
+        let mut writer = request.writer(*b"PolQ").ok_or_else(|| {
+            io::const_io_error!(io::ErrorKind::InvalidInput, &"Unable to create request")
+        })?;
+        // Round up, so that a short timeout doesn't turn into a poll that returns
+        // immediately.
+        writer.append(timeout.map(|timeout| {
+            u64::try_from(timeout.as_nanos().div_ceil(1_000_000)).unwrap_or(u64::MAX)
+        }));
+        writer.append(
+            sources
+                .iter()
+                .map(|(source, interest)| (source.fd, source.kind as u8, *interest))
+                .collect::<Vec<(u16, u8, u8)>>(),
+        );
+    }
+
+    let (err, _) = request
+        .lend_mut(services::net_server(), services::NetLendMut::StdPoll.into())
+        .or(Err(io::const_io_error!(io::ErrorKind::InvalidInput, &"Internal error")))?;
+    if err != 0 {
+        return Err(if err == NetError::Invalid as usize {
+            io::const_io_error!(io::ErrorKind::InvalidInput, &"Invalid socket")
+        } else {
+            io::const_io_error!(io::ErrorKind::Other, &"Unable to poll sockets")
+        });
+    }
+
+    let reader = request.reader(*b"PolR").ok_or_else(|| {
+        io::const_io_error!(io::ErrorKind::InvalidData, &"Invalid response from server")
+    })?;
+    let response = reader.try_get_from::<Vec<u8>>().or(Err(io::const_io_error!(
+        io::ErrorKind::InvalidData,
+        &"Invalid response from server"
+    )))?;
+    if response.len() != sources.len() {
+        return Err(io::const_io_error!(
+            io::ErrorKind::InvalidData,
+            &"Invalid response from server"
+        ));
+    }
+
+    let mut count = 0;
+    for ((readiness, (_, interest)), slot) in response.iter().zip(sources).zip(ready.iter_mut()) {
+        *slot = readiness & interest;
+        if *slot != 0 {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
@@ -105,6 +105,12 @@ fn bind_inner(addr: &mut SocketAddr) -> io::Result<u16> {
         Ok(fd.try_into().unwrap())
     }
 
+    pub fn poll_source(&self) -> PollSource {
+        // The listener gets a new fd every time a connection is accepted,
+        // so always look up the current one.
+        PollSource { fd: self.fd.load(Ordering::Relaxed), kind: SocketKind::TcpListener }
+    }
+
     pub fn socket_addr(&self) -> io::Result<SocketAddr> {
         Ok(self.local)
     }
@@ -214,8 +220,8 @@ pub fn only_v6(&self) -> io::Result<bool> {
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
-        // this call doesn't have a meaning on our platform, but we can at least not panic if it's used.
-        Ok(None)
+        socket_option(services::NetBlockingScalar::StdTakeErrorTcp(self.fd.load(Ordering::Relaxed)))
+            .map(|res| if res[1] != 0 { Some(net_error(res[1])) } else { None })
     }
 
     pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
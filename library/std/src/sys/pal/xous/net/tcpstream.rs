@@ -2,25 +2,42 @@
 use crate::fmt;
 use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut};
 use crate::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, SocketAddrV4, SocketAddrV6};
+use crate::os::xous::ipc::{PageBuffer, PAGE_SIZE};
 use crate::os::xous::services;
 use crate::sync::Arc;
 use crate::time::Duration;
 use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 
-macro_rules! unimpl {
-    () => {
-        return Err(io::const_io_error!(
-            io::ErrorKind::Unsupported,
-            &"This function is not yet implemented",
-        ));
-    };
-}
-
 enum ReadOrPeek {
     Read,
     Peek,
 }
 
+/// Cleared once the network server turns out not to support lending more than a
+/// page at a time, after which writes go out one page per message instead.
+static TX_PAGES_SUPPORTED: AtomicBool = AtomicBool::new(true);
+
+/// The most data that is moved by a single message. Larger transfers are split
+/// up by the caller, just like a short read or write on other platforms.
+const MAX_TRANSFER_SIZE: usize = 16 * PAGE_SIZE;
+
+/// Whether `buf` may be lent to the server as-is, without copying it into a
+/// buffer of our own first.
+fn is_lendable(buf: &[u8]) -> bool {
+    !buf.is_empty() && buf.as_ptr().addr() % PAGE_SIZE == 0 && buf.len() % PAGE_SIZE == 0
+}
+
+/// Convert an error code from a send operation into an `io::Error`.
+fn send_error(code: u8) -> io::Error {
+    if code == NetError::TimedOut as u8 {
+        io::const_io_error!(io::ErrorKind::BrokenPipe, &"Timeout or connection closed")
+    } else if code == NetError::WouldBlock as u8 {
+        io::const_io_error!(io::ErrorKind::WouldBlock, &"Would block")
+    } else {
+        io::const_io_error!(io::ErrorKind::InvalidInput, &"Error when sending")
+    }
+}
+
 #[derive(Clone)]
 pub struct TcpStream {
     fd: u16,
@@ -178,9 +195,30 @@ pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
     }
 
     fn read_or_peek(&self, buf: &mut [u8], op: ReadOrPeek) -> io::Result<usize> {
-        let mut receive_request = ReceiveData { raw: [0u8; 4096] };
-        let data_to_read = buf.len().min(receive_request.raw.len());
+        let data_to_read = buf.len().min(MAX_TRANSFER_SIZE);
 
+        // Lend the caller's memory directly if possible
+        if is_lendable(buf) {
+            return self.receive(&mut buf[..data_to_read], data_to_read, op);
+        }
+
+        let length = if data_to_read <= PAGE_SIZE {
+            let mut receive_request = ReceiveData { raw: [0u8; 4096] };
+            let length = self.receive(&mut receive_request.raw, data_to_read, op)?;
+            buf[..length].copy_from_slice(&receive_request.raw[..length]);
+            length
+        } else {
+            let mut receive_request = PageBuffer::new(data_to_read);
+            let length = self.receive(&mut receive_request, data_to_read, op)?;
+            buf[..length].copy_from_slice(&receive_request[..length]);
+            length
+        };
+        Ok(length)
+    }
+
+    /// Lend `pages` to the server to be filled with at most `data_to_read` bytes.
+    /// `pages` must be page-aligned and a whole number of pages long.
+    fn receive(&self, pages: &mut [u8], data_to_read: usize, op: ReadOrPeek) -> io::Result<usize> {
         let opcode = match op {
             ReadOrPeek::Read => {
                 services::NetLendMut::StdTcpRx(self.fd, self.nonblocking.load(Ordering::Relaxed))
@@ -193,7 +231,7 @@ fn read_or_peek(&self, buf: &mut [u8], op: ReadOrPeek) -> io::Result<usize> {
         let Ok((offset, length)) = crate::os::xous::ffi::lend_mut(
             services::net_server(),
             opcode.into(),
-            &mut receive_request.raw,
+            pages,
             // Reuse the `offset` as the read timeout
             self.read_timeout.load(Ordering::Relaxed) as usize,
             data_to_read,
@@ -205,18 +243,14 @@ fn read_or_peek(&self, buf: &mut [u8], op: ReadOrPeek) -> io::Result<usize> {
         };
 
         if offset != 0 {
-            for (dest, src) in buf.iter_mut().zip(receive_request.raw[..length].iter()) {
-                *dest = *src;
-            }
-            Ok(length)
+            Ok(length.min(data_to_read))
         } else {
-            let result = receive_request.raw;
-            if result[0] != 0 {
-                if result[1] == 8 {
+            if pages[0] != 0 {
+                if pages[1] == 8 {
                     // timed out
                     return Err(io::const_io_error!(io::ErrorKind::TimedOut, &"Timeout",));
                 }
-                if result[1] == 9 {
+                if pages[1] == 9 {
                     // would block
                     return Err(io::const_io_error!(io::ErrorKind::WouldBlock, &"Would block",));
                 }
@@ -234,7 +268,29 @@ pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
     }
 
     pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
-        crate::io::default_read_vectored(|b| self.read(b), bufs)
+        // A single buffer may be able to be lent directly
+        if let [buf] = bufs {
+            return self.read(buf);
+        }
+        let data_to_read =
+            bufs.iter().map(|buf| buf.len()).fold(0, usize::saturating_add).min(MAX_TRANSFER_SIZE);
+        if data_to_read == 0 {
+            return Ok(0);
+        }
+
+        // Receive everything in one message, then scatter it across the buffers
+        let mut receive_request = PageBuffer::new(data_to_read);
+        let length = self.receive(&mut receive_request, data_to_read, ReadOrPeek::Read)?;
+        let mut received = &receive_request[..length];
+        for buf in bufs {
+            let count = received.len().min(buf.len());
+            buf[..count].copy_from_slice(&received[..count]);
+            received = &received[count..];
+            if received.is_empty() {
+                break;
+            }
+        }
+        Ok(length)
     }
 
     pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
@@ -242,15 +298,35 @@ pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
     }
 
     pub fn is_read_vectored(&self) -> bool {
-        false
+        true
     }
 
     pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
-        let mut send_request = SendData { raw: [0u8; 4096] };
-        for (dest, src) in send_request.raw.iter_mut().zip(buf) {
-            *dest = *src;
+        let buf_len = buf.len().min(MAX_TRANSFER_SIZE);
+
+        if TX_PAGES_SUPPORTED.load(Ordering::Relaxed) {
+            // Lend the caller's memory directly if possible
+            let sent = if is_lendable(buf) {
+                self.send_pages(&buf[..buf_len], buf_len)
+            } else if buf_len > PAGE_SIZE {
+                let mut send_request = PageBuffer::new(buf_len);
+                send_request[..buf_len].copy_from_slice(&buf[..buf_len]);
+                self.send_pages(&send_request, buf_len)
+            } else {
+                None
+            };
+            if let Some(sent) = sent {
+                return sent;
+            }
         }
-        let buf_len = send_request.raw.len().min(buf.len());
+        self.send_page(buf)
+    }
+
+    /// Copy up to a page of `buf` into a message of its own and send it.
+    fn send_page(&self, buf: &[u8]) -> io::Result<usize> {
+        let buf_len = buf.len().min(PAGE_SIZE);
+        let mut send_request = SendData { raw: [0u8; 4096] };
+        send_request.raw[..buf_len].copy_from_slice(&buf[..buf_len]);
 
         let (_offset, _valid) = crate::os::xous::ffi::lend_mut(
             services::net_server(),
@@ -263,21 +339,7 @@ pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
         .or(Err(io::const_io_error!(io::ErrorKind::InvalidInput, &"Internal error")))?;
 
         if send_request.raw[0] != 0 {
-            if send_request.raw[4] == 8 {
-                // timed out
-                return Err(io::const_io_error!(
-                    io::ErrorKind::BrokenPipe,
-                    &"Timeout or connection closed",
-                ));
-            } else if send_request.raw[4] == 9 {
-                // would block
-                return Err(io::const_io_error!(io::ErrorKind::WouldBlock, &"Would block",));
-            } else {
-                return Err(io::const_io_error!(
-                    io::ErrorKind::InvalidInput,
-                    &"Error when sending",
-                ));
-            }
+            return Err(send_error(send_request.raw[4]));
         }
         Ok(u32::from_le_bytes([
             send_request.raw[4],
@@ -287,12 +349,65 @@ pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
         ]) as usize)
     }
 
+    /// Lend `pages` to the server as read-only memory and send the first `buf_len`
+    /// bytes. `pages` must be page-aligned and a whole number of pages long.
+    /// Returns `None` if the server doesn't support this, in which case the data
+    /// has to be sent with `send_page()` instead.
+    fn send_pages(&self, pages: &[u8], buf_len: usize) -> Option<io::Result<usize>> {
+        let Ok((result, sent)) = crate::os::xous::ffi::lend(
+            services::net_server(),
+            services::NetLend::StdTcpTxPages(self.fd).into(),
+            pages,
+            // Reuse the offset as the timeout
+            self.write_timeout.load(Ordering::Relaxed) as usize,
+            buf_len,
+        ) else {
+            TX_PAGES_SUPPORTED.store(false, Ordering::Relaxed);
+            return None;
+        };
+
+        if result != 0 {
+            return Some(Err(send_error(result as u8)));
+        }
+        Some(Ok(sent.min(buf_len)))
+    }
+
     pub fn write_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
-        crate::io::default_write_vectored(|b| self.write(b), bufs)
+        // A single buffer may be able to be lent directly
+        if let [buf] = bufs {
+            return self.write(buf);
+        }
+        if !TX_PAGES_SUPPORTED.load(Ordering::Relaxed) {
+            return crate::io::default_write_vectored(|buf| self.write(buf), bufs);
+        }
+        let buf_len =
+            bufs.iter().map(|buf| buf.len()).fold(0, usize::saturating_add).min(MAX_TRANSFER_SIZE);
+        if buf_len == 0 {
+            return Ok(0);
+        }
+
+        // Gather everything into one buffer so it goes out in a single message
+        let mut send_request = PageBuffer::new(buf_len);
+        let mut remaining = &mut send_request[..buf_len];
+        for buf in bufs {
+            let count = remaining.len().min(buf.len());
+            let (dest, rest) = remaining.split_at_mut(count);
+            dest.copy_from_slice(&buf[..count]);
+            remaining = rest;
+            if remaining.is_empty() {
+                break;
+            }
+        }
+        self.send_pages(&send_request, buf_len)
+            .unwrap_or_else(|| self.send_page(&send_request[..buf_len]))
     }
 
     pub fn is_write_vectored(&self) -> bool {
-        false
+        true
+    }
+
+    pub fn poll_source(&self) -> PollSource {
+        PollSource { fd: self.fd, kind: SocketKind::TcpStream }
     }
 
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
@@ -347,12 +462,38 @@ pub fn duplicate(&self) -> io::Result<TcpStream> {
         Ok(self.clone())
     }
 
-    pub fn set_linger(&self, _: Option<Duration>) -> io::Result<()> {
-        unimpl!();
+    pub fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        socket_option(services::NetBlockingScalar::StdSetLinger(self.fd, linger)).map(|_| ())
     }
 
     pub fn linger(&self) -> io::Result<Option<Duration>> {
-        unimpl!();
+        socket_option(services::NetBlockingScalar::StdGetLinger(self.fd)).map(|res| {
+            if res[1] != 0 {
+                Some(Duration::from_millis(res[2] as u64))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn set_keepalive(&self, interval: Option<Duration>) -> io::Result<()> {
+        if interval == Some(Duration::ZERO) {
+            return Err(io::const_io_error!(
+                io::ErrorKind::InvalidInput,
+                &"Keepalive interval must be nonzero",
+            ));
+        }
+        socket_option(services::NetBlockingScalar::StdSetKeepalive(self.fd, interval)).map(|_| ())
+    }
+
+    pub fn keepalive(&self) -> io::Result<Option<Duration>> {
+        socket_option(services::NetBlockingScalar::StdGetKeepalive(self.fd)).map(|res| {
+            if res[1] != 0 {
+                Some(Duration::from_millis(res[2] as u64))
+            } else {
+                None
+            }
+        })
     }
 
     pub fn set_nodelay(&self, enabled: bool) -> io::Result<()> {
@@ -395,8 +536,13 @@ pub fn ttl(&self) -> io::Result<u32> {
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
-        // this call doesn't have a meaning on our platform, but we can at least not panic if it's used.
-        Ok(None)
+        socket_option(services::NetBlockingScalar::StdTakeErrorTcp(self.fd)).map(|res| {
+            if res[1] != 0 {
+                Some(net_error(res[1]))
+            } else {
+                None
+            }
+        })
     }
 
     pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
@@ -9,15 +9,6 @@
 use core::convert::TryInto;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-macro_rules! unimpl {
-    () => {
-        return Err(io::const_io_error!(
-            io::ErrorKind::Unsupported,
-            &"This function is not yet implemented",
-        ));
-    };
-}
-
 #[derive(Clone)]
 pub struct UdpSocket {
     fd: u16,
@@ -101,6 +92,10 @@ pub fn bind(socketaddr: io::Result<&SocketAddr>) -> io::Result<UdpSocket> {
         Err(io::const_io_error!(io::ErrorKind::InvalidInput, &"Invalid response"))
     }
 
+    pub fn poll_source(&self) -> PollSource {
+        PollSource { fd: self.fd, kind: SocketKind::Udp }
+    }
+
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         match self.remote.get() {
             Some(dest) => Ok(dest),
@@ -386,8 +381,13 @@ pub fn ttl(&self) -> io::Result<u32> {
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
-        // this call doesn't have a meaning on our platform, but we can at least not panic if it's used.
-        Ok(None)
+        socket_option(services::NetBlockingScalar::StdTakeErrorUdp(self.fd)).map(|res| {
+            if res[1] != 0 {
+                Some(net_error(res[1]))
+            } else {
+                None
+            }
+        })
     }
 
     pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
@@ -395,53 +395,91 @@ pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
         Ok(())
     }
 
-    // ------------- smoltcp base stack does not have multicast or broadcast support ---------------
-    pub fn set_broadcast(&self, _: bool) -> io::Result<()> {
-        unimpl!();
+    pub fn set_broadcast(&self, broadcast: bool) -> io::Result<()> {
+        socket_option(services::NetBlockingScalar::StdSetBroadcast(self.fd, broadcast)).map(|_| ())
     }
 
     pub fn broadcast(&self) -> io::Result<bool> {
-        unimpl!();
+        socket_option(services::NetBlockingScalar::StdGetBroadcast(self.fd)).map(|res| res[1] != 0)
     }
 
-    pub fn set_multicast_loop_v4(&self, _: bool) -> io::Result<()> {
-        unimpl!();
+    pub fn set_multicast_loop_v4(&self, multicast_loop_v4: bool) -> io::Result<()> {
+        socket_option(services::NetBlockingScalar::StdSetMulticastLoop(
+            self.fd,
+            multicast_loop_v4,
+            false,
+        ))
+        .map(|_| ())
     }
 
     pub fn multicast_loop_v4(&self) -> io::Result<bool> {
-        unimpl!();
+        socket_option(services::NetBlockingScalar::StdGetMulticastLoop(self.fd, false))
+            .map(|res| res[1] != 0)
     }
 
-    pub fn set_multicast_ttl_v4(&self, _: u32) -> io::Result<()> {
-        unimpl!();
+    pub fn set_multicast_ttl_v4(&self, multicast_ttl_v4: u32) -> io::Result<()> {
+        if multicast_ttl_v4 > 255 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "TTL must be less than 256"));
+        }
+        socket_option(services::NetBlockingScalar::StdSetMulticastTtl(self.fd, multicast_ttl_v4))
+            .map(|_| ())
     }
 
     pub fn multicast_ttl_v4(&self) -> io::Result<u32> {
-        unimpl!();
+        socket_option(services::NetBlockingScalar::StdGetMulticastTtl(self.fd))
+            .map(|res| res[1] as _)
     }
 
-    pub fn set_multicast_loop_v6(&self, _: bool) -> io::Result<()> {
-        unimpl!();
+    pub fn set_multicast_loop_v6(&self, multicast_loop_v6: bool) -> io::Result<()> {
+        socket_option(services::NetBlockingScalar::StdSetMulticastLoop(
+            self.fd,
+            multicast_loop_v6,
+            true,
+        ))
+        .map(|_| ())
     }
 
     pub fn multicast_loop_v6(&self) -> io::Result<bool> {
-        unimpl!();
+        socket_option(services::NetBlockingScalar::StdGetMulticastLoop(self.fd, true))
+            .map(|res| res[1] != 0)
+    }
+
+    pub fn join_multicast_v4(&self, multiaddr: &Ipv4Addr, interface: &Ipv4Addr) -> io::Result<()> {
+        socket_option(services::NetBlockingScalar::StdJoinMulticastV4(
+            self.fd, *multiaddr, *interface,
+        ))
+        .map(|_| ())
     }
 
-    pub fn join_multicast_v4(&self, _: &Ipv4Addr, _: &Ipv4Addr) -> io::Result<()> {
-        unimpl!();
+    pub fn join_multicast_v6(&self, multiaddr: &Ipv6Addr, interface: u32) -> io::Result<()> {
+        Self::check_v6_interface(interface)?;
+        socket_option(services::NetBlockingScalar::StdJoinMulticastV6(self.fd, *multiaddr))
+            .map(|_| ())
     }
 
-    pub fn join_multicast_v6(&self, _: &Ipv6Addr, _: u32) -> io::Result<()> {
-        unimpl!();
+    pub fn leave_multicast_v4(&self, multiaddr: &Ipv4Addr, interface: &Ipv4Addr) -> io::Result<()> {
+        socket_option(services::NetBlockingScalar::StdLeaveMulticastV4(
+            self.fd, *multiaddr, *interface,
+        ))
+        .map(|_| ())
     }
 
-    pub fn leave_multicast_v4(&self, _: &Ipv4Addr, _: &Ipv4Addr) -> io::Result<()> {
-        unimpl!();
+    pub fn leave_multicast_v6(&self, multiaddr: &Ipv6Addr, interface: u32) -> io::Result<()> {
+        Self::check_v6_interface(interface)?;
+        socket_option(services::NetBlockingScalar::StdLeaveMulticastV6(self.fd, *multiaddr))
+            .map(|_| ())
     }
 
-    pub fn leave_multicast_v6(&self, _: &Ipv6Addr, _: u32) -> io::Result<()> {
-        unimpl!();
+    /// Xous only has a single network interface, so the only valid interface
+    /// index is 0, which means "the default interface".
+    fn check_v6_interface(interface: u32) -> io::Result<()> {
+        if interface != 0 {
+            return Err(io::const_io_error!(
+                io::ErrorKind::InvalidInput,
+                &"Unknown interface index"
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -1,24 +1,29 @@
-use super::unsupported;
+use crate::borrow::Cow;
 use crate::collections::HashMap;
 use crate::error::Error as StdError;
 use crate::ffi::{OsStr, OsString};
 use crate::fmt;
 use crate::io;
-use crate::marker::PhantomData;
-use crate::os::xous::ffi::Error as XousError;
-use crate::path::{self, PathBuf};
+use crate::iter;
+use crate::os::xous::ffi::{Error as XousError, OsStrExt, OsStringExt};
+use crate::path::{self, Path, PathBuf};
+use crate::slice;
 use crate::sync::{
-    atomic::{AtomicPtr, AtomicUsize, Ordering},
-    Mutex, Once,
+    atomic::{AtomicPtr, AtomicU32, AtomicUsize, Ordering},
+    Mutex, Once, OnceLock,
 };
 use crate::vec;
 
 pub(crate) mod params;
 
+#[cfg(test)]
+mod tests;
+
 static PARAMS_ADDRESS: AtomicPtr<u8> = AtomicPtr::new(core::ptr::null_mut());
 
+// The frame finder is installed even when panics abort, because it is also
+// what lets `Backtrace` walk the stack.
 #[cfg(not(test))]
-#[cfg(feature = "panic_unwind")]
 mod eh_unwinding {
     pub(crate) struct EhFrameFinder;
     pub(crate) static mut EH_FRAME_ADDRESS: usize = 0;
@@ -52,11 +57,8 @@ pub extern "C" fn abort() {
 
     #[no_mangle]
     pub extern "C" fn _start(eh_frame: usize, params_address: usize) {
-        #[cfg(feature = "panic_unwind")]
-        {
-            unsafe { super::eh_unwinding::EH_FRAME_ADDRESS = eh_frame };
-            unwind::set_custom_eh_frame_finder(&super::eh_unwinding::EH_FRAME_SETTINGS).ok();
-        }
+        unsafe { super::eh_unwinding::EH_FRAME_ADDRESS = eh_frame };
+        unwind::set_custom_eh_frame_finder(&super::eh_unwinding::EH_FRAME_SETTINGS).ok();
 
         if params_address != 0 {
             let params_address = crate::ptr::with_exposed_provenance_mut::<u8>(params_address);
@@ -66,6 +68,7 @@ pub extern "C" fn _start(eh_frame: usize, params_address: usize) {
                 super::PARAMS_ADDRESS.store(params_address, core::sync::atomic::Ordering::Relaxed);
             }
         }
+        crate::sys::stack_overflow::init();
         exit(unsafe { main() });
     }
 
@@ -78,6 +81,19 @@ pub extern "C" fn __rust_abort() -> ! {
     }
 }
 
+/// The address `_start` was loaded at. Programs may be relocated by the loader,
+/// so this is reported alongside backtraces to let a host tool line them up
+/// with the ELF file.
+#[cfg(not(test))]
+pub(crate) fn load_base() -> usize {
+    c_compat::_start as usize
+}
+
+#[cfg(test)]
+pub(crate) fn load_base() -> usize {
+    0
+}
+
 pub fn errno() -> i32 {
     0
 }
@@ -86,53 +102,168 @@ pub fn error_string(errno: i32) -> String {
     Into::<XousError>::into(errno).to_string()
 }
 
+// ---------- Working dict ---------- //
+
+/// The working dict until `chdir()` is called. Relative paths are handed to the
+/// PDDB as they are, which looks for them in every open basis.
+const ROOT_DICT: &str = "::";
+
+/// The absolute path of the dict that relative paths start from.
+fn working_dict() -> &'static Mutex<String> {
+    static WORKING_DICT: OnceLock<Mutex<String>> = OnceLock::new();
+    WORKING_DICT.get_or_init(|| {
+        let inherited = get_application_parameters().and_then(|mut params| {
+            params.find_map(|param| params::WorkingDict::try_from(&param).ok())
+        });
+        Mutex::new(inherited.map_or(ROOT_DICT, |dict| dict.path).to_owned())
+    })
+}
+
+/// Resolve a relative `path` against the working dict. Absolute paths are
+/// returned unchanged, as is everything while the working dict is the root.
+pub(crate) fn resolve_path(path: &str) -> Cow<'_, str> {
+    if path.starts_with(path::MAIN_SEPARATOR) {
+        return Cow::Borrowed(path);
+    }
+    let dict = working_dict().lock().unwrap();
+    if *dict == ROOT_DICT {
+        return Cow::Borrowed(path);
+    }
+    let mut resolved = dict.clone();
+    if !path.is_empty() {
+        resolved.push(path::MAIN_SEPARATOR);
+        resolved.push_str(path);
+    }
+    Cow::Owned(resolved)
+}
+
+/// Turn `path` into an absolute path that can be used as a working dict,
+/// making sure that it names a basis or a dict.
+fn dict_path(path: &Path) -> io::Result<String> {
+    let path = path
+        .to_str()
+        .ok_or(io::const_io_error!(io::ErrorKind::InvalidFilename, &"path is not valid UTF-8"))?;
+    let resolved = resolve_path(path);
+    match crate::sys::path::split_basis_and_dict(&resolved, || None) {
+        Ok((None, None)) => return Ok(ROOT_DICT.to_owned()),
+        Ok(_) => {}
+        Err(()) => {
+            return Err(io::const_io_error!(io::ErrorKind::InvalidFilename, &"invalid path"));
+        }
+    }
+    if !crate::sys::fs::stat(Path::new(&*resolved))?.file_type().is_dir() {
+        return Err(io::const_io_error!(io::ErrorKind::NotADirectory, &"not a dict"));
+    }
+
+    // A relative path only gets this far unchanged when it was resolved against
+    // the root, so spell out that it may be found in any basis.
+    Ok(if resolved.starts_with(path::MAIN_SEPARATOR) {
+        resolved.into_owned()
+    } else {
+        format!("{ROOT_DICT}{resolved}")
+    })
+}
+
+/// Return the working dict that a child process should start in, which is
+/// `cwd` if one was given and this process's own otherwise. Children start at
+/// the root when they aren't told anything, so that returns `None`.
+pub(crate) fn child_working_dict(cwd: Option<&Path>) -> io::Result<Option<String>> {
+    let dict = match cwd {
+        Some(cwd) => dict_path(cwd)?,
+        None => working_dict().lock().unwrap().clone(),
+    };
+    Ok(if dict == ROOT_DICT { None } else { Some(dict) })
+}
+
 pub fn getcwd() -> io::Result<PathBuf> {
-    unsupported()
+    Ok(PathBuf::from(working_dict().lock().unwrap().as_str()))
 }
 
-pub fn chdir(_: &path::Path) -> io::Result<()> {
-    unsupported()
+pub fn chdir(p: &Path) -> io::Result<()> {
+    let dict = dict_path(p)?;
+    *working_dict().lock().unwrap() = dict;
+    Ok(())
 }
 
-pub struct SplitPaths<'a>(!, PhantomData<&'a ()>);
+/// Separates the entries of a search path such as `PATH`. Xous paths use `:`
+/// between their components, so that can't be used here as it is on Unix.
+const PATH_LIST_SEPARATOR: u8 = b';';
 
-pub fn split_paths(_unparsed: &OsStr) -> SplitPaths<'_> {
-    panic!("unsupported")
+pub struct SplitPaths<'a> {
+    iter: iter::Map<slice::Split<'a, u8, fn(&u8) -> bool>, fn(&'a [u8]) -> PathBuf>,
+}
+
+pub fn split_paths(unparsed: &OsStr) -> SplitPaths<'_> {
+    fn bytes_to_path(b: &[u8]) -> PathBuf {
+        PathBuf::from(<OsStr as OsStrExt>::from_bytes(b))
+    }
+    fn is_separator(b: &u8) -> bool {
+        *b == PATH_LIST_SEPARATOR
+    }
+    let unparsed = unparsed.as_bytes();
+    SplitPaths {
+        iter: unparsed
+            .split(is_separator as fn(&u8) -> bool)
+            .map(bytes_to_path as fn(&[u8]) -> PathBuf),
+    }
 }
 
 impl<'a> Iterator for SplitPaths<'a> {
     type Item = PathBuf;
     fn next(&mut self) -> Option<PathBuf> {
-        self.0
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
     }
 }
 
 #[derive(Debug)]
 pub struct JoinPathsError;
 
-pub fn join_paths<I, T>(_paths: I) -> Result<OsString, JoinPathsError>
+pub fn join_paths<I, T>(paths: I) -> Result<OsString, JoinPathsError>
 where
     I: Iterator<Item = T>,
     T: AsRef<OsStr>,
 {
-    Err(JoinPathsError)
+    let mut joined = Vec::new();
+
+    for (i, path) in paths.enumerate() {
+        let path = path.as_ref().as_bytes();
+        if i > 0 {
+            joined.push(PATH_LIST_SEPARATOR)
+        }
+        if path.contains(&PATH_LIST_SEPARATOR) {
+            return Err(JoinPathsError);
+        }
+        joined.extend_from_slice(path);
+    }
+    Ok(OsStringExt::from_vec(joined))
 }
 
 impl fmt::Display for JoinPathsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        "not supported on this platform yet".fmt(f)
+        write!(f, "path segment contains separator `{}`", char::from(PATH_LIST_SEPARATOR))
     }
 }
 
 impl StdError for JoinPathsError {
     #[allow(deprecated)]
     fn description(&self) -> &str {
-        "not supported on this platform yet"
+        "failed to join paths"
     }
 }
 
 pub fn current_exe() -> io::Result<PathBuf> {
-    unsupported()
+    get_application_parameters()
+        .and_then(|mut params| {
+            params.find_map(|param| params::ExecutableName::try_from(&param).ok())
+        })
+        .map(|exe| PathBuf::from(exe.name))
+        .ok_or(io::const_io_error!(
+            io::ErrorKind::NotFound,
+            &"the loader did not pass the name of this program"
+        ))
 }
 
 pub(crate) fn get_application_parameters() -> Option<params::ApplicationParameters> {
@@ -234,8 +365,34 @@ pub unsafe fn unsetenv(k: &OsStr) -> io::Result<()> {
     Ok(())
 }
 
+/// The basis that holds temporary files. It lives in RAM, so nothing in it
+/// survives a reboot.
+const TEMP_BASIS: &str = ".Temp";
+const TEMP_DICT: &str = ":.Temp:tmp";
+
 pub fn temp_dir() -> PathBuf {
-    panic!("no filesystem on this platform")
+    if let Some(dir) = getenv(OsStr::new("TMPDIR")) {
+        return dir.into();
+    }
+
+    static TEMP_DIR: OnceLock<PathBuf> = OnceLock::new();
+    TEMP_DIR
+        .get_or_init(|| {
+            // Both of these fail if another process got there first, which is fine,
+            // so check whether the dict is there afterwards instead.
+            crate::sys::fs::create_volatile_basis(TEMP_BASIS).ok();
+            crate::sys::fs::DirBuilder::new().mkdir(Path::new(TEMP_DICT)).ok();
+            if crate::sys::fs::stat(Path::new(TEMP_DICT))
+                .is_ok_and(|attr| attr.file_type().is_dir())
+            {
+                return PathBuf::from(TEMP_DICT);
+            }
+
+            // Without a volatile basis, temporary files have to go somewhere that
+            // exists, even though they'll outlive a reboot there.
+            getcwd().unwrap_or_else(|_| PathBuf::from(ROOT_DICT))
+        })
+        .clone()
 }
 
 pub fn home_dir() -> Option<PathBuf> {
@@ -247,5 +404,15 @@ pub fn exit(code: i32) -> ! {
 }
 
 pub fn getpid() -> u32 {
-    panic!("no pids on this platform")
+    // The id never changes, so only ask the kernel once it's been given out.
+    static PROCESS_ID: AtomicU32 = AtomicU32::new(0);
+    let pid = PROCESS_ID.load(Ordering::Relaxed);
+    if pid != 0 {
+        return pid;
+    }
+
+    // No process has an id of 0, so report that if the kernel can't be asked.
+    let pid = crate::os::xous::ffi::process_id().unwrap_or(0);
+    PROCESS_ID.store(pid, Ordering::Relaxed);
+    pid
 }
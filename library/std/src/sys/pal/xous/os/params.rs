@@ -68,6 +68,69 @@
 /// They are a sequence of the form:
 ///
 ///      (u16 /* val_len */ [0u8; val_len as usize])
+///
+/// #[repr(C)]
+/// struct StdioBlock {
+///     magic: b"StdP",
+///
+///     /// Total number of bytes, excluding this header
+///     size: 6,
+///
+///     /// Pipe IDs for stdin, stdout, and stderr respectively. A value of `0`
+///     /// inherits the console, and a value of `0xffff` discards all data.
+///     stdin: u16,
+///     stdout: u16,
+///     stderr: u16,
+/// }
+///
+/// A `StdP` block is only present when the parent process redirected the
+/// standard streams of this process when spawning it.
+///
+/// #[repr(C)]
+/// struct StackBlock {
+///     magic: b"StkB",
+///
+///     /// Total number of bytes, excluding this header
+///     size: 12,
+///
+///     /// The lowest address of the main thread's stack, and its size in bytes.
+///     start: u32,
+///     length: u32,
+///
+///     /// Bit 0 is set if the page below the stack is reserved as a guard page,
+///     /// so that the process may take away access to it. Older loaders leave
+///     /// this field out, in which case `size` is 8.
+///     flags: u32,
+/// }
+///
+/// The loader adds a `StkB` block to describe where it put the main thread's
+/// stack, so that it can be guarded the same way as any other thread's.
+///
+/// #[repr(C)]
+/// struct ExecutableName {
+///     magic: b"ExeN",
+///
+///     /// Total number of bytes, excluding this header
+///     size: data.len(),
+///
+///     /// The name the program was started with, as UTF-8
+///     data: [u8; 0],
+/// }
+///
+/// #[repr(C)]
+/// struct WorkingDict {
+///     magic: b"WrkD",
+///
+///     /// Total number of bytes, excluding this header
+///     size: data.len(),
+///
+///     /// The absolute path of the dict that relative paths start from, as UTF-8
+///     data: [u8; 0],
+/// }
+///
+/// A `WrkD` block is only present when the process should start somewhere
+/// other than the root of the open bases.
+use core::ops::Range;
 use core::slice;
 
 /// Magic number indicating we have an environment block
@@ -79,6 +142,24 @@
 /// Magic number indicating the loader has passed application parameters
 const PARAMS_MAGIC: [u8; 4] = *b"AppP";
 
+/// Standard I/O redirection set up by the parent process
+const STDIO_MAGIC: [u8; 4] = *b"StdP";
+
+/// Location of the main thread's stack
+const STACK_MAGIC: [u8; 4] = *b"StkB";
+
+/// The page below the main thread's stack is reserved as its guard page
+const STACK_FLAG_GUARD_RESERVED: u32 = 1;
+
+/// Name of the program that is running
+const EXE_MAGIC: [u8; 4] = *b"ExeN";
+
+/// Working dict inherited from the parent process
+const WORKING_DICT_MAGIC: [u8; 4] = *b"WrkD";
+
+#[cfg(test)]
+mod test;
+
 pub(crate) struct ApplicationParameters {
     data: &'static [u8],
     offset: usize,
@@ -265,3 +346,201 @@ fn next(&mut self) -> Option<Self::Item> {
         Some(ArgumentEntry { value })
     }
 }
+
+/// The pipe a standard stream was redirected to by the parent process.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum StdioRedirect {
+    /// Use the console, as if no redirection took place
+    Inherit,
+    /// Discard all output, and report end-of-file on input
+    Null,
+    /// Read from or write to the pipe with the given ID
+    Pipe(u16),
+}
+
+impl From<u16> for StdioRedirect {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => StdioRedirect::Inherit,
+            0xffff => StdioRedirect::Null,
+            id => StdioRedirect::Pipe(id),
+        }
+    }
+}
+
+impl Into<u16> for StdioRedirect {
+    fn into(self) -> u16 {
+        match self {
+            StdioRedirect::Inherit => 0,
+            StdioRedirect::Null => 0xffff,
+            StdioRedirect::Pipe(id) => id,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct StdioBlock {
+    pub stdin: StdioRedirect,
+    pub stdout: StdioRedirect,
+    pub stderr: StdioRedirect,
+}
+
+impl TryFrom<&ApplicationParameter> for StdioBlock {
+    type Error = ApplicationParameterError;
+
+    fn try_from(value: &ApplicationParameter) -> Result<Self, Self::Error> {
+        if value.data.len() < 6 || value.magic != STDIO_MAGIC {
+            return Err(ApplicationParameterError);
+        }
+        let stream = |index: usize| {
+            u16::from_le_bytes(value.data[index * 2..index * 2 + 2].try_into().unwrap()).into()
+        };
+        Ok(StdioBlock { stdin: stream(0), stdout: stream(1), stderr: stream(2) })
+    }
+}
+
+pub(crate) struct StackBlock {
+    pub bounds: Range<usize>,
+    pub guard_reserved: bool,
+}
+
+impl TryFrom<&ApplicationParameter> for StackBlock {
+    type Error = ApplicationParameterError;
+
+    fn try_from(value: &ApplicationParameter) -> Result<Self, Self::Error> {
+        if value.data.len() < 8 || value.magic != STACK_MAGIC {
+            return Err(ApplicationParameterError);
+        }
+        let start = u32::from_le_bytes(value.data[0..4].try_into().unwrap()) as usize;
+        let length = u32::from_le_bytes(value.data[4..8].try_into().unwrap()) as usize;
+        let flags =
+            value.data.get(8..12).map_or(0, |flags| u32::from_le_bytes(flags.try_into().unwrap()));
+        Ok(StackBlock {
+            bounds: start..start.checked_add(length).ok_or(ApplicationParameterError)?,
+            guard_reserved: flags & STACK_FLAG_GUARD_RESERVED != 0,
+        })
+    }
+}
+
+pub(crate) struct ExecutableName {
+    pub name: &'static str,
+}
+
+impl TryFrom<&ApplicationParameter> for ExecutableName {
+    type Error = ApplicationParameterError;
+
+    fn try_from(value: &ApplicationParameter) -> Result<Self, Self::Error> {
+        if value.magic != EXE_MAGIC {
+            return Err(ApplicationParameterError);
+        }
+        let name = core::str::from_utf8(value.data).or(Err(ApplicationParameterError))?;
+        Ok(ExecutableName { name })
+    }
+}
+
+pub(crate) struct WorkingDict {
+    pub path: &'static str,
+}
+
+impl TryFrom<&ApplicationParameter> for WorkingDict {
+    type Error = ApplicationParameterError;
+
+    fn try_from(value: &ApplicationParameter) -> Result<Self, Self::Error> {
+        if value.magic != WORKING_DICT_MAGIC {
+            return Err(ApplicationParameterError);
+        }
+        let path = core::str::from_utf8(value.data).or(Err(ApplicationParameterError))?;
+        Ok(WorkingDict { path })
+    }
+}
+
+/// Serializes a parameter block that may be handed to a child process. This is
+/// the inverse of `ApplicationParameters`, and produces the same layout that the
+/// loader passes to `_start`.
+pub(crate) struct ApplicationParametersWriter {
+    data: Vec<u8>,
+    entries: u32,
+}
+
+impl ApplicationParametersWriter {
+    pub(crate) fn new() -> Self {
+        let mut data = Vec::new();
+        data.extend_from_slice(&PARAMS_MAGIC);
+        data.extend_from_slice(&8u32.to_le_bytes());
+        // Total length and entry count get filled in by `finish()`
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        ApplicationParametersWriter { data, entries: 1 }
+    }
+
+    fn append_block(
+        &mut self,
+        magic: [u8; 4],
+        contents: &[u8],
+    ) -> Result<(), ApplicationParameterError> {
+        let size: u32 = contents.len().try_into().or(Err(ApplicationParameterError))?;
+        self.data.extend_from_slice(&magic);
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(contents);
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Append a length-prefixed string, as used by both `ArgL` and `EnvB` blocks.
+    fn append_str(block: &mut Vec<u8>, value: &[u8]) -> Result<(), ApplicationParameterError> {
+        let len: u16 = value.len().try_into().or(Err(ApplicationParameterError))?;
+        block.extend_from_slice(&len.to_le_bytes());
+        block.extend_from_slice(value);
+        Ok(())
+    }
+
+    pub(crate) fn arguments<'a, I: ExactSizeIterator<Item = &'a [u8]>>(
+        &mut self,
+        args: I,
+    ) -> Result<(), ApplicationParameterError> {
+        let count: u16 = args.len().try_into().or(Err(ApplicationParameterError))?;
+        let mut block = count.to_le_bytes().to_vec();
+        for arg in args {
+            Self::append_str(&mut block, arg)?;
+        }
+        self.append_block(ARGS_MAGIC, &block)
+    }
+
+    pub(crate) fn environment<'a, I: ExactSizeIterator<Item = (&'a [u8], &'a [u8])>>(
+        &mut self,
+        envs: I,
+    ) -> Result<(), ApplicationParameterError> {
+        let count: u16 = envs.len().try_into().or(Err(ApplicationParameterError))?;
+        let mut block = count.to_le_bytes().to_vec();
+        for (key, value) in envs {
+            Self::append_str(&mut block, key)?;
+            Self::append_str(&mut block, value)?;
+        }
+        self.append_block(ENV_MAGIC, &block)
+    }
+
+    pub(crate) fn stdio(&mut self, stdio: StdioBlock) -> Result<(), ApplicationParameterError> {
+        let mut block = [0u8; 6];
+        for (dest, stream) in
+            block.chunks_exact_mut(2).zip([stdio.stdin, stdio.stdout, stdio.stderr])
+        {
+            dest.copy_from_slice(&Into::<u16>::into(stream).to_le_bytes());
+        }
+        self.append_block(STDIO_MAGIC, &block)
+    }
+
+    pub(crate) fn executable(&mut self, name: &str) -> Result<(), ApplicationParameterError> {
+        self.append_block(EXE_MAGIC, name.as_bytes())
+    }
+
+    pub(crate) fn working_dict(&mut self, path: &str) -> Result<(), ApplicationParameterError> {
+        self.append_block(WORKING_DICT_MAGIC, path.as_bytes())
+    }
+
+    pub(crate) fn finish(mut self) -> Result<Vec<u8>, ApplicationParameterError> {
+        let length: u32 = self.data.len().try_into().or(Err(ApplicationParameterError))?;
+        self.data[8..12].copy_from_slice(&length.to_le_bytes());
+        self.data[12..16].copy_from_slice(&self.entries.to_le_bytes());
+        Ok(self.data)
+    }
+}
@@ -1,7 +1,9 @@
 use super::*;
-use crate::{collections::HashMap, io::Write};
+use crate::collections::HashMap;
+use crate::io::{self, Write};
+use crate::ptr;
 
-fn create_args_test() -> std::io::Result<Vec<u8>> {
+fn create_args_test() -> io::Result<Vec<u8>> {
     let mut sample_data = vec![];
     let mut h = HashMap::new();
 
@@ -12,9 +14,11 @@ fn create_args_test() -> std::io::Result<Vec<u8>> {
     // Magic number
     sample_data.write_all(&PARAMS_MAGIC)?;
     // Size of the AppP block
-    sample_data.write_all(&4u32.to_le_bytes())?;
+    sample_data.write_all(&8u32.to_le_bytes())?;
+    // Total length, filled in once everything has been written
+    sample_data.write_all(&0u32.to_le_bytes())?;
     // Number of blocks
-    sample_data.write_all(&2u32.to_le_bytes())?;
+    sample_data.write_all(&3u32.to_le_bytes())?;
 
     // Magic number
     sample_data.write_all(&ENV_MAGIC)?;
@@ -48,9 +52,19 @@ fn create_args_test() -> std::io::Result<Vec<u8>> {
         sample_data.write_all(entry.as_bytes())?;
     }
 
+    let length = sample_data.len() as u32;
+    sample_data[8..12].copy_from_slice(&length.to_le_bytes());
     Ok(sample_data)
 }
 
+/// Parse a block the same way `_start` does. The parsed entries borrow from the
+/// block for the life of the process, so the block is leaked.
+fn parse(data: Vec<u8>) -> ApplicationParameters {
+    let data = data.leak();
+    unsafe { ApplicationParameters::new_from_ptr(data.as_ptr()) }
+        .expect("Unable to parse arguments")
+}
+
 #[test]
 fn basic_arg_parsing() {
     let arg_data = create_args_test().expect("couldn't create test data");
@@ -59,7 +73,7 @@ fn basic_arg_parsing() {
     }
     println!();
 
-    let args = ApplicationParameters::new(&arg_data).expect("Unable to parse arguments");
+    let args = parse(arg_data);
     for arg in args {
         if let Ok(env) = EnvironmentBlock::try_from(&arg) {
             for env in env {
@@ -72,3 +86,122 @@ fn basic_arg_parsing() {
         }
     }
 }
+
+#[test]
+fn rejects_bad_header() {
+    let mut data = create_args_test().unwrap();
+    data[0] = b'X';
+    assert!(unsafe { ApplicationParameters::new_from_ptr(data.as_ptr()) }.is_none());
+    assert!(unsafe { ApplicationParameters::new_from_ptr(ptr::null()) }.is_none());
+}
+
+#[test]
+fn writer_round_trip() {
+    let mut writer = ApplicationParametersWriter::new();
+    writer.arguments([&b"prog"[..], b"with spaces", b""].into_iter()).ok().unwrap();
+    writer.environment([(&b"KEY"[..], &b"value"[..]), (b"EMPTY", b"")].into_iter()).ok().unwrap();
+    let data = writer.finish().ok().unwrap();
+
+    let mut args = vec![];
+    let mut envs = vec![];
+    let mut blocks = 0;
+    for param in parse(data) {
+        blocks += 1;
+        if let Ok(list) = ArgumentList::try_from(&param) {
+            args.extend(list.map(|arg| arg.value));
+        } else if let Ok(env) = EnvironmentBlock::try_from(&param) {
+            envs.extend(env.map(|entry| (entry.key, entry.value)));
+        }
+    }
+    assert_eq!(blocks, 2);
+    assert_eq!(args, ["prog", "with spaces", ""]);
+    assert_eq!(envs, [("KEY", "value"), ("EMPTY", "")]);
+}
+
+#[test]
+fn stdio_round_trip() {
+    let stdio = StdioBlock {
+        stdin: StdioRedirect::Null,
+        stdout: StdioRedirect::Pipe(7),
+        stderr: StdioRedirect::Inherit,
+    };
+    let mut writer = ApplicationParametersWriter::new();
+    writer.stdio(stdio).ok().unwrap();
+    let data = writer.finish().ok().unwrap();
+
+    let parsed = parse(data).find_map(|param| StdioBlock::try_from(&param).ok()).unwrap();
+    assert_eq!(parsed.stdin, StdioRedirect::Null);
+    assert_eq!(parsed.stdout, StdioRedirect::Pipe(7));
+    assert_eq!(parsed.stderr, StdioRedirect::Inherit);
+}
+
+#[test]
+fn stdio_redirect_encoding() {
+    for redirect in [StdioRedirect::Inherit, StdioRedirect::Null, StdioRedirect::Pipe(1)] {
+        assert_eq!(StdioRedirect::from(Into::<u16>::into(redirect)), redirect);
+    }
+    // These ids can't be handed to a child as pipes
+    assert_ne!(StdioRedirect::from(0), StdioRedirect::Pipe(0));
+    assert_ne!(StdioRedirect::from(0xffff), StdioRedirect::Pipe(0xffff));
+}
+
+#[test]
+fn short_stdio_block() {
+    let mut writer = ApplicationParametersWriter::new();
+    writer.append_block(STDIO_MAGIC, &[0, 0, 0, 0]).ok().unwrap();
+    let data = writer.finish().ok().unwrap();
+    assert!(parse(data).all(|param| StdioBlock::try_from(&param).is_err()));
+}
+
+fn stack_block(contents: &[u8]) -> Option<StackBlock> {
+    let mut writer = ApplicationParametersWriter::new();
+    writer.append_block(STACK_MAGIC, contents).ok().unwrap();
+    let data = writer.finish().ok().unwrap();
+    parse(data).find_map(|param| StackBlock::try_from(&param).ok())
+}
+
+#[test]
+fn stack_block_parsing() {
+    let mut contents = vec![];
+    contents.extend_from_slice(&0x4000_0000u32.to_le_bytes());
+    contents.extend_from_slice(&0x2_0000u32.to_le_bytes());
+
+    // Older loaders don't say whether the guard page is reserved
+    let stack = stack_block(&contents).unwrap();
+    assert_eq!(stack.bounds, 0x4000_0000..0x4002_0000);
+    assert!(!stack.guard_reserved);
+
+    contents.extend_from_slice(&STACK_FLAG_GUARD_RESERVED.to_le_bytes());
+    let stack = stack_block(&contents).unwrap();
+    assert_eq!(stack.bounds, 0x4000_0000..0x4002_0000);
+    assert!(stack.guard_reserved);
+
+    assert!(stack_block(&contents[..6]).is_none());
+}
+
+#[test]
+fn executable_and_working_dict_round_trip() {
+    let mut writer = ApplicationParametersWriter::new();
+    writer.executable("shellchat").ok().unwrap();
+    writer.working_dict(":Basis:some dict").ok().unwrap();
+    let data = writer.finish().ok().unwrap();
+
+    let params: Vec<_> = parse(data).collect();
+    let exe = params.iter().find_map(|param| ExecutableName::try_from(param).ok()).unwrap();
+    assert_eq!(exe.name, "shellchat");
+    let dict = params.iter().find_map(|param| WorkingDict::try_from(param).ok()).unwrap();
+    assert_eq!(dict.path, ":Basis:some dict");
+}
+
+#[test]
+fn non_utf8_names_are_rejected() {
+    let mut writer = ApplicationParametersWriter::new();
+    writer.append_block(EXE_MAGIC, &[b'a', 0xff]).ok().unwrap();
+    writer.append_block(WORKING_DICT_MAGIC, &[0xc0]).ok().unwrap();
+    let data = writer.finish().ok().unwrap();
+
+    for param in parse(data) {
+        assert!(ExecutableName::try_from(&param).is_err());
+        assert!(WorkingDict::try_from(&param).is_err());
+    }
+}
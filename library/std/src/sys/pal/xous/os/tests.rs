@@ -0,0 +1,51 @@
+use super::*;
+
+// The working dict is shared by the whole process, so everything that changes
+// it is kept in a single test.
+#[test]
+fn resolve_against_working_dict() {
+    assert_eq!(resolve_path("dict:key"), "dict:key");
+    assert_eq!(resolve_path(":Basis:dict:key"), ":Basis:dict:key");
+
+    let previous = crate::mem::replace(&mut *working_dict().lock().unwrap(), ":Basis:dict".into());
+    assert_eq!(resolve_path("key"), ":Basis:dict:key");
+    assert_eq!(resolve_path("inner:key"), ":Basis:dict:inner:key");
+    assert_eq!(resolve_path(""), ":Basis:dict");
+    assert_eq!(resolve_path("::other:key"), "::other:key");
+    assert_eq!(child_working_dict(None).unwrap().as_deref(), Some(":Basis:dict"));
+
+    *working_dict().lock().unwrap() = previous;
+    assert_eq!(child_working_dict(None).unwrap(), None);
+}
+
+#[test]
+fn dict_path_of_root() {
+    assert_eq!(dict_path(Path::new("::")).unwrap(), ROOT_DICT);
+    assert_eq!(dict_path(Path::new(":")).unwrap(), ROOT_DICT);
+}
+
+#[test]
+fn dict_path_rejects_invalid_paths() {
+    let err = dict_path(Path::new(OsStr::from_bytes(&[b'd', 0xff]))).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidFilename);
+    let err = dict_path(Path::new(":Basis:dict:")).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidFilename);
+}
+
+#[test]
+fn split_paths_on_semicolons() {
+    let paths: Vec<_> = split_paths(OsStr::new(":Sys:bin;apps;;::tools")).collect();
+    assert_eq!(paths, [":Sys:bin", "apps", "", "::tools"].map(PathBuf::from));
+    assert_eq!(split_paths(OsStr::new("")).collect::<Vec<_>>(), [PathBuf::new()]);
+}
+
+#[test]
+fn join_paths_round_trip() {
+    let joined = join_paths([":Sys:bin", "apps", "", "::tools"].iter()).unwrap();
+    assert_eq!(joined, ":Sys:bin;apps;;::tools");
+    let paths: Vec<_> = split_paths(&joined).collect();
+    assert_eq!(paths, [":Sys:bin", "apps", "", "::tools"].map(PathBuf::from));
+
+    assert!(join_paths(["a;b"].iter()).is_err());
+    assert_eq!(join_paths(crate::iter::empty::<&str>()).unwrap(), "");
+}
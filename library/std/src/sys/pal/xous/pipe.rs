@@ -0,0 +1,179 @@
+use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut};
+use crate::os::xous::ffi::{blocking_scalar, lend, lend_mut};
+use crate::os::xous::services::{
+    loader_server, LoaderBlockingScalar, LoaderLend, LoaderLendMut, PipeEnd,
+};
+
+use super::os::params::StdioRedirect;
+
+/// Pipes are owned by the loader server. Data moves through them one page at
+/// a time, with each page lent to the server for the duration of the call.
+#[repr(C, align(4096))]
+struct PipeBuffer([u8; 4096]);
+
+#[derive(Debug)]
+pub struct AnonPipe {
+    id: u16,
+    end: PipeEnd,
+}
+
+/// Create a new pipe, returning the read end and the write end, in that order.
+pub(crate) fn anon_pipe() -> io::Result<(AnonPipe, AnonPipe)> {
+    let result = blocking_scalar(loader_server()?, LoaderBlockingScalar::CreatePipe.into())
+        .or(Err(io::const_io_error!(io::ErrorKind::Other, &"unable to contact process loader")))?;
+    if result[0] != 0 {
+        return Err(io::const_io_error!(io::ErrorKind::OutOfMemory, &"unable to allocate pipe"));
+    }
+    let Ok(id) = u16::try_from(result[1]) else {
+        return Err(io::const_io_error!(io::ErrorKind::Other, &"pipe id out of range"));
+    };
+    let pipes = (AnonPipe { id, end: PipeEnd::Read }, AnonPipe { id, end: PipeEnd::Write });
+
+    // These ids stand for the console and for discarded output when handed to a
+    // child, so a pipe that got one can't be told apart from them. Close it again.
+    if StdioRedirect::from(id) != StdioRedirect::Pipe(id) {
+        return Err(io::const_io_error!(io::ErrorKind::Other, &"pipe id is reserved"));
+    }
+    Ok(pipes)
+}
+
+/// Read from the pipe with the given `id`. Returns `Ok(0)` once the write end
+/// has been closed and all data has been drained.
+pub(crate) fn read_pipe(id: u16, buf: &mut [u8], nonblocking: bool) -> io::Result<usize> {
+    let mut buffer = PipeBuffer([0u8; 4096]);
+    let buffer_len = buffer.0.len();
+
+    let (offset, valid) = lend_mut(
+        loader_server()?,
+        LoaderLendMut::PipeRead(id, nonblocking).into(),
+        &mut buffer.0,
+        0,
+        buf.len().min(buffer_len),
+    )
+    .or(Err(io::const_io_error!(io::ErrorKind::Other, &"unable to read from pipe")))?;
+
+    match offset {
+        0 => {}
+        // Pipe is empty, but the write end is still open
+        1 => return Err(io::const_io_error!(io::ErrorKind::WouldBlock, &"pipe is empty")),
+        _ => return Err(io::const_io_error!(io::ErrorKind::Other, &"unable to read from pipe")),
+    }
+
+    let valid = valid.min(buf.len()).min(buffer_len);
+    buf[..valid].copy_from_slice(&buffer.0[..valid]);
+    Ok(valid)
+}
+
+/// Write to the pipe with the given `id`. At most one page is transferred per call.
+pub(crate) fn write_pipe(id: u16, buf: &[u8]) -> io::Result<usize> {
+    let mut buffer = PipeBuffer([0u8; 4096]);
+    let valid = buf.len().min(buffer.0.len());
+    buffer.0[..valid].copy_from_slice(&buf[..valid]);
+
+    let (offset, written) =
+        lend(loader_server()?, LoaderLend::PipeWrite(id).into(), &buffer.0, 0, valid)
+            .or(Err(io::const_io_error!(io::ErrorKind::Other, &"unable to write to pipe")))?;
+
+    match offset {
+        0 => Ok(written.min(valid)),
+        // The read end has been closed
+        1 => Err(io::const_io_error!(io::ErrorKind::BrokenPipe, &"pipe closed")),
+        _ => Err(io::const_io_error!(io::ErrorKind::Other, &"unable to write to pipe")),
+    }
+}
+
+impl AnonPipe {
+    pub(crate) fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Give up ownership of this end of the pipe without closing it. This is used
+    /// when the pipe is handed to a child process, which then becomes responsible
+    /// for it.
+    pub(crate) fn into_id(self) -> u16 {
+        let id = self.id;
+        crate::mem::forget(self);
+        id
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.end != PipeEnd::Read {
+            return Err(io::const_io_error!(
+                io::ErrorKind::PermissionDenied,
+                &"cannot read from the write end of a pipe"
+            ));
+        }
+        read_pipe(self.id, buf, false)
+    }
+
+    pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
+        crate::io::default_read_buf(|buf| self.read(buf), cursor)
+    }
+
+    pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        crate::io::default_read_vectored(|buf| self.read(buf), bufs)
+    }
+
+    pub fn is_read_vectored(&self) -> bool {
+        false
+    }
+
+    pub fn read_to_end(&self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut chunk = [0u8; 4096];
+        let mut total = 0;
+        loop {
+            match self.read(&mut chunk) {
+                Ok(0) => return Ok(total),
+                Ok(len) => {
+                    buf.extend_from_slice(&chunk[..len]);
+                    total += len;
+                }
+                Err(e) if e.is_interrupted() => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        if self.end != PipeEnd::Write {
+            return Err(io::const_io_error!(
+                io::ErrorKind::PermissionDenied,
+                &"cannot write to the read end of a pipe"
+            ));
+        }
+        write_pipe(self.id, buf)
+    }
+
+    pub fn write_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        crate::io::default_write_vectored(|buf| self.write(buf), bufs)
+    }
+
+    pub fn is_write_vectored(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for AnonPipe {
+    fn drop(&mut self) {
+        // A pipe can only have been created through the loader, so it's connected.
+        if let Ok(loader) = loader_server() {
+            blocking_scalar(loader, LoaderBlockingScalar::ClosePipe(self.id, self.end).into()).ok();
+        }
+    }
+}
+
+pub fn read2(p1: AnonPipe, v1: &mut Vec<u8>, p2: AnonPipe, v2: &mut Vec<u8>) -> io::Result<()> {
+    // There is no way to wait on two pipes at once, so drain the second pipe on
+    // a helper thread. Otherwise a child that fills one pipe while we are blocked
+    // reading the other would deadlock.
+    let reader = crate::thread::Builder::new().spawn(move || {
+        let mut data = vec![];
+        p2.read_to_end(&mut data).map(|_| data)
+    })?;
+    let result = p1.read_to_end(v1);
+    let mut data = reader
+        .join()
+        .map_err(|_| io::const_io_error!(io::ErrorKind::Other, &"pipe reader thread panicked"))??;
+    v2.append(&mut data);
+    result.map(|_| ())
+}
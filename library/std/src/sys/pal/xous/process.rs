@@ -0,0 +1,556 @@
+use crate::ffi::{OsStr, OsString};
+use crate::fmt;
+use crate::io;
+use crate::num::NonZero;
+use crate::os::xous::ffi::{blocking_scalar, OsStrExt};
+use crate::os::xous::senres::{self, Senres, SenresMut};
+use crate::os::xous::services::{loader_server, LoaderBlockingScalar, LoaderLendMut};
+use crate::path::Path;
+use crate::sys::fs::File;
+use crate::sys::pipe::{anon_pipe, read2, AnonPipe};
+use crate::sys::stdio::stdio_redirects;
+use crate::sys_common::process::{CommandEnv, CommandEnvs};
+
+use super::os::child_working_dict;
+use super::os::params::{ApplicationParametersWriter, StdioBlock, StdioRedirect};
+
+pub use crate::ffi::OsString as EnvKey;
+
+////////////////////////////////////////////////////////////////////////////////
+// Command
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct Command {
+    program: OsString,
+    args: Vec<OsString>,
+    env: CommandEnv,
+
+    cwd: Option<OsString>,
+    stdin: Option<Stdio>,
+    stdout: Option<Stdio>,
+    stderr: Option<Stdio>,
+}
+
+// passed back to std::process with the pipes connected to the child, if any
+// were requested
+pub struct StdioPipes {
+    pub stdin: Option<AnonPipe>,
+    pub stdout: Option<AnonPipe>,
+    pub stderr: Option<AnonPipe>,
+}
+
+#[derive(Debug)]
+pub enum Stdio {
+    Inherit,
+    Null,
+    MakePipe,
+    ParentStdout,
+    ParentStderr,
+    Pipe(AnonPipe),
+    #[allow(dead_code)] // This variant exists only for the Debug impl
+    InheritFile(File),
+}
+
+/// The size of the spawn request. This must be large enough to hold the program
+/// name as well as the serialized arguments and environment.
+const SPAWN_REQUEST_SIZE: usize = 16384;
+
+/// A standard stream as it will be handed to the child process. Pipe ends
+/// given to the child are only released once the child has been spawned, so
+/// that they are closed again if spawning fails.
+enum ChildStdio {
+    /// Go wherever the same stream of this process goes.
+    Inherit(StdioRedirect),
+    Null,
+    Pipe(AnonPipe),
+}
+
+impl ChildStdio {
+    fn redirect(&self) -> StdioRedirect {
+        match self {
+            ChildStdio::Inherit(redirect) => *redirect,
+            ChildStdio::Null => StdioRedirect::Null,
+            ChildStdio::Pipe(pipe) => StdioRedirect::Pipe(pipe.id()),
+        }
+    }
+
+    /// Hand ownership of any pipe to the child process.
+    fn release(self) {
+        if let ChildStdio::Pipe(pipe) = self {
+            pipe.into_id();
+        }
+    }
+}
+
+impl Stdio {
+    /// Turn this `Stdio` into the stream that the child will see, along with
+    /// the end of the pipe that the parent keeps, if any.
+    ///
+    /// `readable` indicates whether the child reads from this stream (stdin)
+    /// rather than writing to it. `parent` holds the redirections of this process,
+    /// of which `inherited` is the one for the same stream, since a child that
+    /// inherits a stream must follow any redirection this process was given.
+    fn to_child_stdio(
+        self,
+        readable: bool,
+        inherited: StdioRedirect,
+        parent: &StdioBlock,
+    ) -> io::Result<(ChildStdio, Option<AnonPipe>)> {
+        match self {
+            Stdio::Inherit => Ok((ChildStdio::Inherit(inherited), None)),
+            Stdio::ParentStdout => Ok((ChildStdio::Inherit(parent.stdout), None)),
+            Stdio::ParentStderr => Ok((ChildStdio::Inherit(parent.stderr), None)),
+            Stdio::Null => Ok((ChildStdio::Null, None)),
+            Stdio::MakePipe => {
+                let (reader, writer) = anon_pipe()?;
+                let (theirs, ours) = if readable { (reader, writer) } else { (writer, reader) };
+                Ok((ChildStdio::Pipe(theirs), Some(ours)))
+            }
+            Stdio::Pipe(pipe) => Ok((ChildStdio::Pipe(pipe), None)),
+            Stdio::InheritFile(_) => Err(io::const_io_error!(
+                io::ErrorKind::Unsupported,
+                &"files cannot be used as standard streams of a child process"
+            )),
+        }
+    }
+
+    /// Pick the `Stdio` to use for the next spawn. Variants that own a resource
+    /// are moved out of `slot`, all others are left in place so that the
+    /// `Command` may be spawned again with the same configuration.
+    fn for_spawn(slot: &mut Option<Stdio>, default: &Stdio) -> Stdio {
+        match slot.as_ref().unwrap_or(default) {
+            Stdio::Inherit => Stdio::Inherit,
+            Stdio::Null => Stdio::Null,
+            Stdio::MakePipe => Stdio::MakePipe,
+            Stdio::ParentStdout => Stdio::ParentStdout,
+            Stdio::ParentStderr => Stdio::ParentStderr,
+            Stdio::Pipe(_) | Stdio::InheritFile(_) => slot.take().unwrap_or(Stdio::Null),
+        }
+    }
+}
+
+impl Command {
+    pub fn new(program: &OsStr) -> Command {
+        Command {
+            program: program.to_owned(),
+            args: vec![program.to_owned()],
+            env: Default::default(),
+            cwd: None,
+            stdin: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    pub fn arg(&mut self, arg: &OsStr) {
+        self.args.push(arg.to_owned());
+    }
+
+    pub fn env_mut(&mut self) -> &mut CommandEnv {
+        &mut self.env
+    }
+
+    pub fn cwd(&mut self, dir: &OsStr) {
+        self.cwd = Some(dir.to_owned());
+    }
+
+    pub fn stdin(&mut self, stdin: Stdio) {
+        self.stdin = Some(stdin);
+    }
+
+    pub fn stdout(&mut self, stdout: Stdio) {
+        self.stdout = Some(stdout);
+    }
+
+    pub fn stderr(&mut self, stderr: Stdio) {
+        self.stderr = Some(stderr);
+    }
+
+    pub fn get_program(&self) -> &OsStr {
+        &self.program
+    }
+
+    pub fn get_args(&self) -> CommandArgs<'_> {
+        let mut iter = self.args.iter();
+        iter.next();
+        CommandArgs { iter }
+    }
+
+    pub fn get_envs(&self) -> CommandEnvs<'_> {
+        self.env.iter()
+    }
+
+    pub fn get_current_dir(&self) -> Option<&Path> {
+        self.cwd.as_ref().map(|cs| Path::new(cs))
+    }
+
+    /// Serialize the program name, arguments, environment, working dict, and stdio
+    /// redirections into a parameter block that the loader hands to the new process.
+    fn parameters(&self, program: &str, stdio: StdioBlock) -> io::Result<Vec<u8>> {
+        let too_long =
+            || io::const_io_error!(io::ErrorKind::ArgumentListTooLong, &"argument list too long");
+        let envs = self.env.capture();
+        let mut writer = ApplicationParametersWriter::new();
+        writer.executable(program).or(Err(too_long()))?;
+        writer.arguments(self.args.iter().map(|arg| arg.as_bytes())).or(Err(too_long()))?;
+        writer
+            .environment(envs.iter().map(|(key, value)| (key.as_bytes(), value.as_bytes())))
+            .or(Err(too_long()))?;
+        if let Some(dict) = child_working_dict(self.get_current_dir())? {
+            writer.working_dict(&dict).or(Err(too_long()))?;
+        }
+        writer.stdio(stdio).or(Err(too_long()))?;
+        writer.finish().or(Err(too_long()))
+    }
+
+    pub fn spawn(
+        &mut self,
+        default: Stdio,
+        needs_stdin: bool,
+    ) -> io::Result<(Process, StdioPipes)> {
+        let program = self.program.to_str().ok_or(io::const_io_error!(
+            io::ErrorKind::InvalidFilename,
+            &"program name is not valid UTF-8"
+        ))?;
+
+        let null = Stdio::Null;
+        let default_stdin = if needs_stdin { &default } else { &null };
+        let stdin = Stdio::for_spawn(&mut self.stdin, default_stdin);
+        let stdout = Stdio::for_spawn(&mut self.stdout, &default);
+        let stderr = Stdio::for_spawn(&mut self.stderr, &default);
+
+        let parent = stdio_redirects().unwrap_or(StdioBlock {
+            stdin: StdioRedirect::Inherit,
+            stdout: StdioRedirect::Inherit,
+            stderr: StdioRedirect::Inherit,
+        });
+        let (child_stdin, our_stdin) = stdin.to_child_stdio(true, parent.stdin, &parent)?;
+        let (child_stdout, our_stdout) = stdout.to_child_stdio(false, parent.stdout, &parent)?;
+        let (child_stderr, our_stderr) = stderr.to_child_stdio(false, parent.stderr, &parent)?;
+        let parameters = self.parameters(
+            program,
+            StdioBlock {
+                stdin: child_stdin.redirect(),
+                stdout: child_stdout.redirect(),
+                stderr: child_stderr.redirect(),
+            },
+        )?;
+
+        if program.len() + parameters.len() + 64 > SPAWN_REQUEST_SIZE {
+            return Err(io::const_io_error!(
+                io::ErrorKind::ArgumentListTooLong,
+                &"argument list too long"
+            ));
+        }
+
+        let mut request = senres::Stack::<SPAWN_REQUEST_SIZE>::new();
+        {
+            let mut writer = request.writer(*b"SpwQ").ok_or_else(|| {
+                io::const_io_error!(io::ErrorKind::Other, &"unable to create request")
+            })?;
+            writer.append(program);
+            writer.append(parameters.as_slice());
+        }
+
+        let (err, _) = request.lend_mut(loader_server()?, LoaderLendMut::SpawnProcess.into()).or(
+            Err(io::const_io_error!(io::ErrorKind::Other, &"unable to contact process loader")),
+        )?;
+
+        match err {
+            0 => {}
+            1 => return Err(io::const_io_error!(io::ErrorKind::NotFound, &"program not found")),
+            2 => {
+                return Err(io::const_io_error!(
+                    io::ErrorKind::OutOfMemory,
+                    &"not enough memory to start process"
+                ));
+            }
+            _ => return Err(io::const_io_error!(io::ErrorKind::Other, &"unable to spawn process")),
+        }
+
+        let reader = request.reader(*b"SpwR").ok_or_else(|| {
+            io::const_io_error!(io::ErrorKind::Other, &"invalid response from process loader")
+        })?;
+        let pid: u32 = reader.try_get_from().or(Err(io::const_io_error!(
+            io::ErrorKind::Other,
+            &"invalid response from process loader"
+        )))?;
+
+        // The child now owns its ends of any pipes
+        child_stdin.release();
+        child_stdout.release();
+        child_stderr.release();
+
+        Ok((
+            Process { pid, status: None },
+            StdioPipes { stdin: our_stdin, stdout: our_stdout, stderr: our_stderr },
+        ))
+    }
+
+    pub fn output(&mut self) -> io::Result<(ExitStatus, Vec<u8>, Vec<u8>)> {
+        let (mut process, mut pipes) = self.spawn(Stdio::MakePipe, false)?;
+
+        drop(pipes.stdin.take());
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        match (pipes.stdout.take(), pipes.stderr.take()) {
+            (None, None) => {}
+            (Some(out), None) => {
+                out.read_to_end(&mut stdout)?;
+            }
+            (None, Some(err)) => {
+                err.read_to_end(&mut stderr)?;
+            }
+            (Some(out), Some(err)) => {
+                read2(out, &mut stdout, err, &mut stderr)?;
+            }
+        }
+
+        let status = process.wait()?;
+        Ok((status, stdout, stderr))
+    }
+}
+
+impl From<AnonPipe> for Stdio {
+    fn from(pipe: AnonPipe) -> Stdio {
+        Stdio::Pipe(pipe)
+    }
+}
+
+impl From<io::Stdout> for Stdio {
+    fn from(_: io::Stdout) -> Stdio {
+        Stdio::ParentStdout
+    }
+}
+
+impl From<io::Stderr> for Stdio {
+    fn from(_: io::Stderr) -> Stdio {
+        Stdio::ParentStderr
+    }
+}
+
+impl From<File> for Stdio {
+    fn from(file: File) -> Stdio {
+        Stdio::InheritFile(file)
+    }
+}
+
+impl fmt::Debug for Command {
+    // show all attributes
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let mut debug_command = f.debug_struct("Command");
+            debug_command.field("program", &self.program).field("args", &self.args);
+            if !self.env.is_unchanged() {
+                debug_command.field("env", &self.env);
+            }
+
+            if self.cwd.is_some() {
+                debug_command.field("cwd", &self.cwd);
+            }
+
+            if self.stdin.is_some() {
+                debug_command.field("stdin", &self.stdin);
+            }
+            if self.stdout.is_some() {
+                debug_command.field("stdout", &self.stdout);
+            }
+            if self.stderr.is_some() {
+                debug_command.field("stderr", &self.stderr);
+            }
+
+            debug_command.finish()
+        } else {
+            if let Some(ref cwd) = self.cwd {
+                write!(f, "cd {cwd:?} && ")?;
+            }
+            if self.env.does_clear() {
+                write!(f, "env -i ")?;
+                // Altered env vars will be printed next, that should exactly work as expected.
+            } else {
+                // Removed env vars need the command to be wrapped in `env`.
+                let mut any_removed = false;
+                for (key, value_opt) in self.get_envs() {
+                    if value_opt.is_none() {
+                        if !any_removed {
+                            write!(f, "env ")?;
+                            any_removed = true;
+                        }
+                        write!(f, "-u {} ", key.to_string_lossy())?;
+                    }
+                }
+            }
+            // Altered env vars can just be added in front of the program.
+            for (key, value_opt) in self.get_envs() {
+                if let Some(value) = value_opt {
+                    write!(f, "{}={value:?} ", key.to_string_lossy())?;
+                }
+            }
+            if self.program != self.args[0] {
+                write!(f, "[{:?}] ", self.program)?;
+            }
+            write!(f, "{:?}", self.args[0])?;
+
+            for arg in &self.args[1..] {
+                write!(f, " {:?}", arg)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct ExitStatus(i32);
+
+impl ExitStatus {
+    pub fn exit_ok(&self) -> Result<(), ExitStatusError> {
+        match NonZero::new(self.0) {
+            None => Ok(()),
+            Some(code) => Err(ExitStatusError(code)),
+        }
+    }
+
+    pub fn code(&self) -> Option<i32> {
+        Some(self.0)
+    }
+}
+
+impl From<usize> for ExitStatus {
+    fn from(code: usize) -> ExitStatus {
+        // Xous processes exit with a `u32`, which is reinterpreted as an `i32`
+        ExitStatus(code as u32 as i32)
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exit status: {}", self.0)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ExitStatusError(NonZero<i32>);
+
+impl Into<ExitStatus> for ExitStatusError {
+    fn into(self) -> ExitStatus {
+        ExitStatus(self.0.get())
+    }
+}
+
+impl ExitStatusError {
+    pub fn code(self) -> Option<NonZero<i32>> {
+        Some(self.0)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ExitCode(u8);
+
+impl ExitCode {
+    pub const SUCCESS: ExitCode = ExitCode(0);
+    pub const FAILURE: ExitCode = ExitCode(1);
+
+    pub fn as_i32(&self) -> i32 {
+        self.0 as i32
+    }
+}
+
+impl From<u8> for ExitCode {
+    fn from(code: u8) -> Self {
+        Self(code)
+    }
+}
+
+pub struct Process {
+    pid: u32,
+    status: Option<ExitStatus>,
+}
+
+impl Process {
+    pub fn id(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn kill(&mut self) -> io::Result<()> {
+        // If the process has already been reaped, its PID may have been reused.
+        if self.status.is_some() {
+            return Ok(());
+        }
+        let result =
+            blocking_scalar(loader_server()?, LoaderBlockingScalar::KillProcess(self.pid).into())
+                .or(Err(io::const_io_error!(
+                io::ErrorKind::Other,
+                &"unable to contact process loader"
+            )))?;
+        match result[0] {
+            // Either the process was killed, or it had already exited
+            0 | 1 => Ok(()),
+            _ => {
+                Err(io::const_io_error!(io::ErrorKind::PermissionDenied, &"unable to kill process"))
+            }
+        }
+    }
+
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        if let Some(status) = self.status {
+            return Ok(status);
+        }
+        let result =
+            blocking_scalar(loader_server()?, LoaderBlockingScalar::WaitProcess(self.pid).into())
+                .or(Err(io::const_io_error!(
+                io::ErrorKind::Other,
+                &"unable to contact process loader"
+            )))?;
+        let status = ExitStatus::from(result[0]);
+        self.status = Some(status);
+        Ok(status)
+    }
+
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status));
+        }
+        let result = blocking_scalar(
+            loader_server()?,
+            LoaderBlockingScalar::TryWaitProcess(self.pid).into(),
+        )
+        .or(Err(io::const_io_error!(io::ErrorKind::Other, &"unable to contact process loader")))?;
+
+        // The first word indicates whether the process has exited, and the
+        // second word contains the exit code if so.
+        if result[0] == 0 {
+            return Ok(None);
+        }
+        let status = ExitStatus::from(result[1]);
+        self.status = Some(status);
+        Ok(Some(status))
+    }
+}
+
+pub struct CommandArgs<'a> {
+    iter: crate::slice::Iter<'a, OsString>,
+}
+
+impl<'a> Iterator for CommandArgs<'a> {
+    type Item = &'a OsStr;
+    fn next(&mut self) -> Option<&'a OsStr> {
+        self.iter.next().map(|os| &**os)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for CommandArgs<'a> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+    fn is_empty(&self) -> bool {
+        self.iter.is_empty()
+    }
+}
+
+impl<'a> fmt::Debug for CommandArgs<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter.clone()).finish()
+    }
+}
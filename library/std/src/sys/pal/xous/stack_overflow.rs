@@ -0,0 +1,149 @@
+//! Stack overflow reporting. Every thread's stack sits just above a guard page
+//! that can't be accessed, so running off the end of a stack faults inside it.
+//! An exception handler looks the faulting address up in a table of guard pages
+//! and reports which thread overflowed. The handler doesn't run on the faulting
+//! thread, so the table can't live in TLS.
+
+use super::os::{get_application_parameters, params::StackBlock};
+use super::stdio::panic_output;
+use super::thread::{set_main_stack_bounds, GUARD_PAGE_SIZE};
+use crate::ffi::{c_char, CStr};
+use crate::fmt;
+use crate::io::Write;
+use crate::os::xous::ffi::{
+    exit, map_memory, set_exception_handler, update_memory_flags, MemoryFlags,
+};
+use crate::ptr;
+use crate::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// The most threads whose guard pages can be tracked at once. Threads beyond this
+/// still have guard pages, but overflowing one is reported as an ordinary fault.
+const MAX_GUARDED_THREADS: usize = 32;
+
+/// Size of the stack that the exception handler runs on.
+const EXCEPTION_STACK_SIZE: usize = 16384;
+
+// Values of `mcause` for faults that come with the data address that was accessed.
+const LOAD_ACCESS_FAULT: usize = 5;
+const STORE_ACCESS_FAULT: usize = 7;
+const LOAD_PAGE_FAULT: usize = 13;
+const STORE_PAGE_FAULT: usize = 15;
+
+struct Guard {
+    /// The lowest address of the guard page, or 0 if this entry is free.
+    start: AtomicUsize,
+
+    /// The name of the thread that owns the stack, or null if it has none.
+    name: AtomicPtr<c_char>,
+}
+
+const EMPTY_GUARD: Guard =
+    Guard { start: AtomicUsize::new(0), name: AtomicPtr::new(ptr::null_mut()) };
+static GUARDS: [Guard; MAX_GUARDED_THREADS] = [EMPTY_GUARD; MAX_GUARDED_THREADS];
+
+fn find_guard(address: usize) -> Option<&'static Guard> {
+    GUARDS.iter().find(|guard| {
+        let start = guard.start.load(Ordering::Acquire);
+        start != 0 && (start..start + GUARD_PAGE_SIZE).contains(&address)
+    })
+}
+
+/// Start watching the guard page at `start`. If the table is full, the thread
+/// goes unwatched.
+pub fn add_guard(start: usize) {
+    for guard in GUARDS.iter() {
+        if guard.start.compare_exchange(0, start, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+            return;
+        }
+    }
+}
+
+/// Stop watching the guard page at `start`. This must be called before the
+/// thread's name is freed.
+pub fn remove_guard(start: usize) {
+    if let Some(guard) = find_guard(start) {
+        guard.name.store(ptr::null_mut(), Ordering::Release);
+        guard.start.store(0, Ordering::Release);
+    }
+}
+
+/// Name the thread that owns the guard page at `start`. `name` must outlive the
+/// call to `remove_guard()`.
+pub fn set_guard_name(start: usize, name: &CStr) {
+    if let Some(guard) = find_guard(start) {
+        guard.name.store(name.as_ptr().cast_mut(), Ordering::Release);
+    }
+}
+
+/// Print `message` as a single panic message and terminate the process, the same
+/// as the kernel would have. Don't use `rtabort!()`, since aborting raises an
+/// exception of its own, and don't report a backtrace, since the only stack
+/// here is the handler's own.
+fn fatal(message: fmt::Arguments<'_>) -> ! {
+    if let Some(mut out) = panic_output() {
+        let _ = out.write_fmt(message);
+    }
+    exit(101);
+}
+
+extern "C" fn handle_exception(code: usize, pc: usize, address: usize) -> ! {
+    if matches!(code, LOAD_ACCESS_FAULT | STORE_ACCESS_FAULT | LOAD_PAGE_FAULT | STORE_PAGE_FAULT) {
+        if let Some(guard) = find_guard(address) {
+            let name = guard.name.load(Ordering::Acquire);
+            let name = if name.is_null() {
+                "<unknown>"
+            } else {
+                unsafe { CStr::from_ptr(name) }.to_str().unwrap_or("<unknown>")
+            };
+            fatal(format_args!(
+                "\nthread '{name}' has overflowed its stack\nfatal runtime error: stack overflow\n"
+            ));
+        }
+    }
+
+    fatal(format_args!(
+        "fatal runtime error: unhandled exception {code} at {pc:#x} (address {address:#x})\n"
+    ));
+}
+
+/// Install the exception handler, and guard the main thread's stack if the
+/// loader said where it is.
+pub fn init() {
+    let Ok(stack) = (unsafe {
+        map_memory::<u8>(None, None, EXCEPTION_STACK_SIZE, MemoryFlags::R | MemoryFlags::W)
+    }) else {
+        return;
+    };
+    if set_exception_handler(handle_exception, stack).is_err() {
+        return;
+    }
+
+    let Some(StackBlock { bounds, guard_reserved }) = get_application_parameters()
+        .and_then(|mut params| params.find_map(|param| StackBlock::try_from(&param).ok()))
+    else {
+        return;
+    };
+    let Some(guard_start) = bounds.start.checked_sub(GUARD_PAGE_SIZE) else {
+        return;
+    };
+
+    // Take away access to the page below the stack, the same as `Thread::new()`
+    // does, but only if the loader set it aside for that. Otherwise it may hold
+    // something else, and is only watched in case it's unmapped and faults anyway.
+    if guard_reserved {
+        unsafe {
+            update_memory_flags(
+                ptr::slice_from_raw_parts_mut(
+                    ptr::with_exposed_provenance_mut::<u8>(guard_start),
+                    GUARD_PAGE_SIZE,
+                ),
+                MemoryFlags::W,
+            )
+            .ok()
+        };
+    }
+
+    add_guard(guard_start);
+    set_guard_name(guard_start, c"main");
+    set_main_stack_bounds(bounds);
+}
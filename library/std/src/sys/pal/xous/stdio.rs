@@ -4,8 +4,32 @@
 pub struct Stdout {}
 pub struct Stderr;
 
-use crate::os::xous::ffi::{lend, try_lend, try_scalar, Connection};
-use crate::os::xous::services::{log_server, try_connect, LogLend, LogScalar};
+use crate::os::xous::ffi::{lend, lend_mut, try_lend, try_scalar, Connection};
+use crate::os::xous::services::{log_server, try_connect, LogLend, LogLendMut, LogScalar};
+use crate::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use crate::sys::pipe::{read_pipe, write_pipe};
+
+use super::os::{get_application_parameters, params::StdioBlock, params::StdioRedirect};
+
+/// Find out where the parent process pointed our standard streams. If this
+/// process was not spawned with redirected streams, everything goes to the console.
+/// The parameter block never changes, so it is only searched once.
+pub(crate) fn stdio_redirects() -> Option<StdioBlock> {
+    static REDIRECTS: OnceLock<Option<StdioBlock>> = OnceLock::new();
+    *REDIRECTS.get_or_init(|| {
+        get_application_parameters()?.find_map(|param| StdioBlock::try_from(&param).ok())
+    })
+}
+
+/// Write all of `buf` to the given pipe, or discard it when redirected to null.
+/// Returns `None` if the stream was not redirected.
+fn write_redirected(redirect: StdioRedirect, buf: &[u8]) -> Option<io::Result<usize>> {
+    match redirect {
+        StdioRedirect::Inherit => None,
+        StdioRedirect::Null => Some(Ok(buf.len())),
+        StdioRedirect::Pipe(id) => Some(write_pipe(id, buf)),
+    }
+}
 
 impl Stdin {
     pub const fn new() -> Stdin {
@@ -14,11 +38,111 @@ pub const fn new() -> Stdin {
 }
 
 impl io::Read for Stdin {
-    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
-        Ok(0)
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match stdio_redirects().map(|redirects| redirects.stdin) {
+            Some(StdioRedirect::Pipe(id)) => return read_pipe(id, buf, false),
+            Some(StdioRedirect::Null) => return Ok(0),
+            Some(StdioRedirect::Inherit) | None => {}
+        }
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        #[repr(C, align(4096))]
+        struct LendBuffer([u8; 4096]);
+        let mut lend_buffer = LendBuffer([0u8; 4096]);
+        let connection = log_server();
+        loop {
+            let to_read = buf.len().min(lend_buffer.0.len());
+            let (closed, valid) = lend_mut(
+                connection,
+                LogLendMut::StandardInput.into(),
+                &mut lend_buffer.0,
+                0,
+                to_read,
+            )
+            .map_err(|_| io::const_io_error!(io::ErrorKind::Other, &"unable to read console"))?;
+
+            // Only report end-of-file once the console has gone away. A read that
+            // returned no data while the console is still open is simply retried.
+            if closed != 0 {
+                return Ok(0);
+            }
+            let valid = valid.min(to_read);
+            if valid != 0 {
+                buf[..valid].copy_from_slice(&lend_buffer.0[..valid]);
+                return Ok(valid);
+            }
+        }
     }
 }
 
+/// Output destined for the console is collected here and only lent to the log
+/// server once a full line or a full page is available, or when explicitly flushed.
+/// There is one of these per stream for the whole process, so output from
+/// short-lived `Stdout` and `Stderr` handles ends up in the same place.
+#[repr(C, align(4096))]
+struct LineBuffer {
+    data: [u8; 4096],
+    len: usize,
+}
+
+static STDOUT_BUFFER: Mutex<LineBuffer> = Mutex::new(LineBuffer::new());
+static STDERR_BUFFER: Mutex<LineBuffer> = Mutex::new(LineBuffer::new());
+
+impl LineBuffer {
+    const fn new() -> LineBuffer {
+        LineBuffer { data: [0u8; 4096], len: 0 }
+    }
+
+    /// Copy as much of `buf` as possible into the buffer, lending it to the log
+    /// server whenever a newline is seen or the page fills up.
+    fn write(&mut self, stream: LogLend, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            let chunk_len = (buf.len() - written).min(self.data.len() - self.len);
+            let chunk = &buf[written..written + chunk_len];
+            self.data[self.len..self.len + chunk_len].copy_from_slice(chunk);
+            self.len += chunk_len;
+
+            if self.len == self.data.len() || chunk.contains(&b'\n') {
+                if let Err(e) = self.flush(stream) {
+                    // Only report the error if none of the caller's data made it out
+                    return if written == 0 { Err(e) } else { Ok(written) };
+                }
+            }
+            written += chunk_len;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self, stream: LogLend) -> io::Result<()> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        let len = self.len;
+        // Whatever happens, the contents of the buffer are gone. Retrying a
+        // server that refused the data once would only stall the caller.
+        self.len = 0;
+        lend(log_server(), stream.into(), &self.data, 0, len)
+            .map(|_| ())
+            .map_err(|_| io::const_io_error!(io::ErrorKind::Other, &"unable to write to console"))
+    }
+}
+
+fn lock_buffer(buffer: &'static Mutex<LineBuffer>) -> MutexGuard<'static, LineBuffer> {
+    // A panic while holding the lock leaves the buffer in a consistent state
+    buffer.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Send any buffered console output to the log server. This is called during
+/// runtime cleanup, when the process is about to exit.
+pub(crate) fn flush_all() {
+    lock_buffer(&STDOUT_BUFFER).flush(LogLend::StandardOutput).ok();
+    lock_buffer(&STDERR_BUFFER).flush(LogLend::StandardError).ok();
+}
+
 impl Stdout {
     pub const fn new() -> Stdout {
         Stdout {}
@@ -27,22 +151,16 @@ pub const fn new() -> Stdout {
 
 impl io::Write for Stdout {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        #[repr(C, align(4096))]
-        struct LendBuffer([u8; 4096]);
-        let mut lend_buffer = LendBuffer([0u8; 4096]);
-        let connection = log_server();
-        for chunk in buf.chunks(lend_buffer.0.len()) {
-            for (dest, src) in lend_buffer.0.iter_mut().zip(chunk) {
-                *dest = *src;
-            }
-            lend(connection, LogLend::StandardOutput.into(), &lend_buffer.0, 0, chunk.len())
-                .unwrap();
+        if let Some(result) =
+            stdio_redirects().and_then(|redirects| write_redirected(redirects.stdout, buf))
+        {
+            return result;
         }
-        Ok(buf.len())
+        lock_buffer(&STDOUT_BUFFER).write(LogLend::StandardOutput, buf)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        Ok(())
+        lock_buffer(&STDOUT_BUFFER).flush(LogLend::StandardOutput)
     }
 }
 
@@ -54,26 +172,21 @@ pub const fn new() -> Stderr {
 
 impl io::Write for Stderr {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        #[repr(C, align(4096))]
-        struct LendBuffer([u8; 4096]);
-        let mut lend_buffer = LendBuffer([0u8; 4096]);
-        let connection = log_server();
-        for chunk in buf.chunks(lend_buffer.0.len()) {
-            for (dest, src) in lend_buffer.0.iter_mut().zip(chunk) {
-                *dest = *src;
-            }
-            lend(connection, LogLend::StandardError.into(), &lend_buffer.0, 0, chunk.len())
-                .unwrap();
+        if let Some(result) =
+            stdio_redirects().and_then(|redirects| write_redirected(redirects.stderr, buf))
+        {
+            return result;
         }
-        Ok(buf.len())
+        lock_buffer(&STDERR_BUFFER).write(LogLend::StandardError, buf)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        Ok(())
+        lock_buffer(&STDERR_BUFFER).flush(LogLend::StandardError)
     }
 }
 
-pub const STDIN_BUF_SIZE: usize = 0;
+/// Console input arrives one page at a time, so there's no benefit to buffering more.
+pub const STDIN_BUF_SIZE: usize = 4096;
 
 pub fn is_ebadf(_err: &io::Error) -> bool {
     true
@@ -122,6 +235,17 @@ pub fn panic_output() -> Option<impl io::Write> {
     // this is likely to succeed.
     let log = log_server();
 
+    // Get anything that was printed before the panic out first, so the panic
+    // message appears after it. Skip a buffer if this thread panicked while
+    // holding its lock, since waiting on it would never finish.
+    for (buffer, stream) in
+        [(&STDOUT_BUFFER, LogLend::StandardOutput), (&STDERR_BUFFER, LogLend::StandardError)]
+    {
+        if let Ok(mut buffer) = buffer.try_lock() {
+            buffer.flush(stream).ok();
+        }
+    }
+
     // Send the "We're panicking" message (1000).
     try_scalar(log, LogScalar::BeginPanic.into()).ok();
 
@@ -1,14 +1,30 @@
+use crate::cell::{Cell, OnceCell};
 use crate::ffi::CStr;
 use crate::io;
 use crate::num::NonZero;
+use crate::ops::Range;
 use crate::os::xous::ffi::{
-    blocking_scalar, create_thread, do_yield, join_thread, map_memory, update_memory_flags,
-    MemoryFlags, Syscall, ThreadId,
+    blocking_scalar, create_thread, do_yield, join_thread, lend, map_memory, thread_id,
+    update_memory_flags, MemoryFlags, Syscall, ThreadId,
 };
-use crate::os::xous::services::{ticktimer_server, TicktimerScalar};
+use crate::os::xous::senres::{self, Senres, SenresMut};
+use crate::os::xous::services::{
+    log_server, ns_timer_supported, ticktimer_server, LogLend, TicktimerScalar,
+};
+use crate::sys::stack_overflow;
 use crate::time::Duration;
 use core::arch::asm;
 
+thread_local! {
+    /// The name of this thread, as set by `Thread::set_name()`.
+    static THREAD_NAME: OnceCell<Box<CStr>> = const { OnceCell::new() };
+
+    /// The usable stack of this thread, not including the guard pages. This is
+    /// unknown for threads that weren't created by `Thread::new()`, other than the
+    /// main thread when the loader describes its stack.
+    static STACK_BOUNDS: Cell<Option<Range<usize>>> = const { Cell::new(None) };
+}
+
 pub struct Thread {
     tid: ThreadId,
 }
@@ -73,11 +89,18 @@ extern "C" fn thread_start(
             guard_page_pre: usize,
             stack_size: usize,
         ) -> ! {
+            let stack_start = guard_page_pre + GUARD_PAGE_SIZE;
+            STACK_BOUNDS.set(Some(stack_start..stack_start + stack_size));
+            stack_overflow::add_guard(guard_page_pre);
+
             unsafe {
                 // Run the contents of the new thread.
                 Box::from_raw(main as *mut Box<dyn FnOnce()>)();
             }
 
+            // The thread's name is about to be freed along with the rest of its TLS.
+            stack_overflow::remove_guard(guard_page_pre);
+
             // Destroy TLS, which will free the TLS page and call the destructor for
             // any thread local storage (if any).
             unsafe {
@@ -109,15 +132,70 @@ pub fn yield_now() {
         do_yield();
     }
 
-    pub fn set_name(_name: &CStr) {
-        // nope
+    pub fn set_name(name: &CStr) {
+        if THREAD_NAME.with(|cell| cell.set(name.into())).is_err() {
+            return;
+        }
+
+        // The exception handler can't reach this thread's TLS, so point it at the
+        // stored copy of the name in case the stack overflows.
+        if let Some(stack) = Self::stack_bounds() {
+            THREAD_NAME.with(|cell| {
+                if let Some(name) = cell.get() {
+                    stack_overflow::set_guard_name(stack.start - GUARD_PAGE_SIZE, name);
+                }
+            });
+        }
+
+        // Let the log server know the name as well, so that it can print it along
+        // with any panic message. This is purely informative, so ignore errors.
+        let Ok(tid) = thread_id() else {
+            return;
+        };
+        let mut buffer = senres::Stack::<4096>::new();
+        let name = name.to_bytes();
+        let name = &name[..name.len().min(buffer.len())];
+        buffer.as_mut_slice()[..name.len()].copy_from_slice(name);
+        lend(log_server(), LogLend::ThreadName.into(), buffer.as_slice(), tid.into(), name.len())
+            .ok();
+    }
+
+    /// Return the range of addresses that make up the stack of the current thread.
+    pub fn stack_bounds() -> Option<Range<usize>> {
+        STACK_BOUNDS.with(|bounds| {
+            let range = bounds.take();
+            bounds.set(range.clone());
+            range
+        })
+    }
+
+    /// Return the kernel's id for the current thread.
+    pub fn current_id() -> io::Result<usize> {
+        thread_id()
+            .map(Into::into)
+            .or(Err(io::const_io_error!(io::ErrorKind::Other, &"unable to get current thread id")))
     }
 
     pub fn sleep(dur: Duration) {
+        if ns_timer_supported() {
+            let mut nanos = dur.as_nanos();
+            while nanos > 0 {
+                let sleep_duration = nanos.min(u64::MAX as u128) as u64;
+                blocking_scalar(
+                    ticktimer_server(),
+                    TicktimerScalar::SleepNs(sleep_duration).into(),
+                )
+                .expect("failed to send message to ticktimer server");
+                nanos -= sleep_duration as u128;
+            }
+            return;
+        }
+
         // Because the sleep server works on units of `usized milliseconds`, split
         // the messages up into these chunks. This means we may run into issues
         // if you try to sleep a thread for more than 49 days on a 32-bit system.
-        let mut millis = dur.as_millis();
+        // Round up so that we never sleep for less than `dur`.
+        let mut millis = dur.as_nanos().div_ceil(1_000_000);
         while millis > 0 {
             let sleep_duration =
                 if millis > (usize::MAX as _) { usize::MAX } else { millis as usize };
@@ -132,6 +210,11 @@ pub fn join(self) {
     }
 }
 
+/// Record where the loader put the main thread's stack.
+pub(crate) fn set_main_stack_bounds(bounds: Range<usize>) {
+    STACK_BOUNDS.set(Some(bounds));
+}
+
 pub fn available_parallelism() -> io::Result<NonZero<usize>> {
     // We're unicore right now.
     Ok(unsafe { NonZero::new_unchecked(1) })
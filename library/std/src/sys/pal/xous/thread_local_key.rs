@@ -9,20 +9,32 @@
 
 /// Thread Local Storage
 ///
-/// Currently, we are limited to 1023 TLS entries. The entries
-/// live in a page of memory that's unique per-process, and is
-/// stored in the `$tp` register. If this register is 0, then
-/// TLS has not been initialized and thread cleanup can be skipped.
+/// The entries live in a chain of pages of memory that's unique per-thread,
+/// the first of which is stored in the `$tp` register. If this register is 0,
+/// then TLS has not been initialized and thread cleanup can be skipped.
 ///
-/// The index into this register is the `key`. This key is identical
-/// between all threads, but indexes a different offset within this
-/// pointer.
+/// The index into this table is the `key`. This key is identical between all
+/// threads, but indexes a different offset within each thread's table. Slot 0
+/// of every page points to the next page of the chain, which is allocated the
+/// first time a key that lives in it is set, so each page holds 1023 keys.
 pub type Key = usize;
 
 pub type Dtor = unsafe extern "C" fn(*mut u8);
 
 const TLS_MEMORY_SIZE: usize = 4096;
 
+/// The number of pointer-sized slots in each page of the table.
+const SLOTS_PER_PAGE: usize = TLS_MEMORY_SIZE / core::mem::size_of::<*mut u8>();
+
+/// Slot 0 of each page holds the link to the next page.
+const KEYS_PER_PAGE: usize = SLOTS_PER_PAGE - 1;
+
+/// The most pages that the table of a single thread may grow to. This caps
+/// the number of keys at 65472, which no reasonable program gets close to.
+const MAX_TLS_PAGES: usize = 64;
+
+const MAX_KEY: Key = KEYS_PER_PAGE * MAX_TLS_PAGES;
+
 /// TLS keys start at `1`. Index `0` is unused
 #[cfg(not(test))]
 #[export_name = "_ZN16__rust_internals3std3sys4xous16thread_local_key13TLS_KEY_INDEXE"]
@@ -52,40 +64,59 @@ fn tls_ptr_addr() -> *mut *mut u8 {
     core::ptr::with_exposed_provenance_mut::<*mut u8>(tp)
 }
 
-/// Create an area of memory that's unique per thread. This area will
-/// contain all thread local pointers.
-fn tls_table() -> &'static mut [*mut u8] {
-    let tp = tls_ptr_addr();
+/// Allocate a new, zeroed page for the TLS table.
+fn allocate_page() -> *mut *mut u8 {
+    let Ok(page) = (unsafe {
+        map_memory::<*mut u8>(None, None, SLOTS_PER_PAGE, MemoryFlags::R | MemoryFlags::W)
+    }) else {
+        rtabort!("unable to allocate memory for thread local storage");
+    };
 
-    if !tp.is_null() {
-        return unsafe {
-            core::slice::from_raw_parts_mut(tp, TLS_MEMORY_SIZE / core::mem::size_of::<*mut u8>())
-        };
+    for val in page.iter() {
+        assert!(val.is_null());
     }
+    page.as_mut_ptr()
+}
+
+/// Return a pointer to the slot that holds `key` in this thread's table. If
+/// `allocate` is `false` and the page containing the slot doesn't exist yet,
+/// then the key has never been set on this thread and `None` is returned.
+/// Otherwise, missing pages are allocated.
+fn tls_slot(key: Key, allocate: bool) -> Option<*mut *mut u8> {
+    assert!((1..=MAX_KEY).contains(&key));
+    let mut page = tls_ptr_addr();
+
     // If the TP register is `0`, then this thread hasn't initialized
     // its TLS yet. Allocate a new page to store this memory.
-    let tp = unsafe {
-        map_memory(
-            None,
-            None,
-            TLS_MEMORY_SIZE / core::mem::size_of::<*mut u8>(),
-            MemoryFlags::R | MemoryFlags::W,
-        )
-        .expect("Unable to allocate memory for thread local storage")
-    };
-
-    for val in tp.iter() {
-        assert!(*val as usize == 0);
+    if page.is_null() {
+        if !allocate {
+            return None;
+        }
+        page = allocate_page();
+        unsafe {
+            // Set the thread's `$tp` register
+            asm!(
+                "mv tp, {}",
+                in(reg) page.expose_provenance(),
+            );
+        }
     }
 
-    unsafe {
-        // Set the thread's `$tp` register
-        asm!(
-            "mv tp, {}",
-            in(reg) tp.as_mut_ptr() as usize,
-        );
+    // Follow the chain to the page that holds this key
+    let index = key - 1;
+    for _ in 0..index / KEYS_PER_PAGE {
+        let next = unsafe { *page }.cast::<*mut u8>();
+        page = if !next.is_null() {
+            next
+        } else if allocate {
+            let next = allocate_page();
+            unsafe { *page = next.cast() };
+            next
+        } else {
+            return None;
+        };
     }
-    tp
+    Some(unsafe { page.add(1 + index % KEYS_PER_PAGE) })
 }
 
 #[inline]
@@ -93,6 +124,9 @@ pub unsafe fn create(dtor: Option<Dtor>) -> Key {
     // Allocate a new TLS key. These keys are shared among all threads.
     #[allow(unused_unsafe)]
     let key = unsafe { TLS_KEY_INDEX.fetch_add(1, Relaxed) };
+    if key > MAX_KEY {
+        rtabort!("out of TLS keys");
+    }
     if let Some(f) = dtor {
         unsafe { register_dtor(key, f) };
     }
@@ -101,15 +135,18 @@ pub unsafe fn create(dtor: Option<Dtor>) -> Key {
 
 #[inline]
 pub unsafe fn set(key: Key, value: *mut u8) {
-    assert!((key < 1022) && (key >= 1));
-    let table = tls_table();
-    table[key] = value;
+    // Don't allocate memory just to store a null pointer in it
+    if let Some(slot) = tls_slot(key, !value.is_null()) {
+        unsafe { *slot = value };
+    }
 }
 
 #[inline]
 pub unsafe fn get(key: Key) -> *mut u8 {
-    assert!((key < 1022) && (key >= 1));
-    tls_table()[key]
+    match tls_slot(key, false) {
+        Some(slot) => unsafe { *slot },
+        None => ptr::null_mut(),
+    }
 }
 
 #[inline]
@@ -175,14 +212,13 @@ pub unsafe fn destroy_tls() {
 
     unsafe { run_dtors() };
 
-    // Finally, free the TLS array
-    unsafe {
-        unmap_memory(core::slice::from_raw_parts_mut(
-            tp,
-            TLS_MEMORY_SIZE / core::mem::size_of::<usize>(),
-        ))
-        .unwrap()
-    };
+    // Finally, free every page of the TLS table
+    let mut page = tp;
+    while !page.is_null() {
+        let next = unsafe { *page }.cast::<*mut u8>();
+        unsafe { unmap_memory(core::ptr::slice_from_raw_parts_mut(page, SLOTS_PER_PAGE)).unwrap() };
+        page = next;
+    }
 }
 
 unsafe fn run_dtors() {
@@ -1,6 +1,9 @@
+use crate::io;
 use crate::os::xous::ffi::blocking_scalar;
 use crate::os::xous::services::{
-    systime_server, ticktimer_server, SystimeScalar::GetUtcTimeMs, TicktimerScalar::ElapsedMs,
+    ns_timer_supported, systime_server, ticktimer_server,
+    SystimeScalar::{GetTzOffsetMs, GetUtcTimeMs, IsTimeSet, SetUtcTimeMs},
+    TicktimerScalar::{ElapsedMs, ElapsedNs},
 };
 use crate::time::Duration;
 
@@ -14,6 +17,14 @@
 
 impl Instant {
     pub fn now() -> Instant {
+        if ns_timer_supported() {
+            let result = blocking_scalar(ticktimer_server(), ElapsedNs.into())
+                .expect("failed to request elapsed_ns");
+            let lower = result[0];
+            let upper = result[1];
+            return Instant { 0: Duration::from_nanos(lower as u64 | (upper as u64) << 32) };
+        }
+
         let result = blocking_scalar(ticktimer_server(), ElapsedMs.into())
             .expect("failed to request elapsed_ms");
         let lower = result[0];
@@ -43,6 +54,15 @@ pub fn now() -> SystemTime {
         SystemTime { 0: Duration::from_millis((upper as u64) << 32 | lower as u64) }
     }
 
+    /// Timestamps are exchanged with other servers as milliseconds since the UNIX epoch.
+    pub(crate) fn from_unix_ms(ms: u64) -> SystemTime {
+        SystemTime(Duration::from_millis(ms))
+    }
+
+    pub(crate) fn as_unix_ms(&self) -> u64 {
+        self.0.as_millis().try_into().unwrap_or(u64::MAX)
+    }
+
     pub fn sub_time(&self, other: &SystemTime) -> Result<Duration, Duration> {
         self.0.checked_sub(other.0).ok_or_else(|| other.0 - self.0)
     }
@@ -55,3 +75,29 @@ pub fn checked_sub_duration(&self, other: &Duration) -> Option<SystemTime> {
         Some(SystemTime(self.0.checked_sub(*other)?))
     }
 }
+
+/// Set the RTC to `time`.
+pub fn set_system_time(time: SystemTime) -> io::Result<()> {
+    let result = blocking_scalar(systime_server(), SetUtcTimeMs(time.as_unix_ms()).into())
+        .or(Err(io::const_io_error!(io::ErrorKind::Other, &"Unable to contact time server")))?;
+    if result[0] != 0 {
+        return Err(io::const_io_error!(io::ErrorKind::Other, &"Unable to set time"));
+    }
+    Ok(())
+}
+
+/// Return the offset of the local time zone from UTC, in milliseconds.
+pub fn local_offset_ms() -> io::Result<i64> {
+    let result = blocking_scalar(systime_server(), GetTzOffsetMs.into())
+        .or(Err(io::const_io_error!(io::ErrorKind::Other, &"Unable to contact time server")))?;
+    let lower = result[0];
+    let upper = result[1];
+    Ok(((upper as u64) << 32 | lower as u64) as i64)
+}
+
+/// Return whether the clock has been set since boot.
+pub fn is_time_set() -> io::Result<bool> {
+    let result = blocking_scalar(systime_server(), IsTimeSet.into())
+        .or(Err(io::const_io_error!(io::ErrorKind::Other, &"Unable to contact time server")))?;
+    Ok(result[0] != 0)
+}
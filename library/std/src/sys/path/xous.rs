@@ -20,9 +20,13 @@ pub fn parse_prefix(_: &OsStr) -> Option<Prefix<'_>> {
 pub const MAIN_SEP_STR: &str = ":";
 pub const MAIN_SEP: char = ':';
 
-/// Make a POSIX path absolute without changing its semantics.
+/// Make a path absolute by prepending the working dict if it's relative. This
+/// doesn't look anything up, so the path doesn't need to exist.
 pub(crate) fn absolute(path: &Path) -> io::Result<PathBuf> {
-    Ok(path.to_owned())
+    if path.has_root() {
+        return Ok(path.to_owned());
+    }
+    Ok(crate::env::current_dir()?.join(path))
 }
 
 /// Split a path into its constituant Basis and Dict, if the path is legal.
@@ -89,10 +89,10 @@ fn index(&self) -> usize {
     }
 
     /// Unlock the given Mutex and wait for the notification. Wait at most
-    /// `ms` milliseconds, or pass `0` to wait forever.
+    /// `timeout`, or pass `None` to wait forever.
     ///
     /// Returns `true` if the condition was received, `false` if it timed out
-    fn wait_ms(&self, mutex: &Mutex, ms: usize) -> bool {
+    fn wait_for(&self, mutex: &Mutex, timeout: Option<Duration>) -> bool {
         self.counter.fetch_add(1, Ordering::Relaxed);
         unsafe { mutex.unlock() };
 
@@ -101,7 +101,7 @@ fn wait_ms(&self, mutex: &Mutex, ms: usize) -> bool {
         // the fact that we're waiting by incrementing the counter.
         let result = blocking_scalar(
             ticktimer_server(),
-            TicktimerScalar::WaitForCondition(self.index(), ms).into(),
+            TicktimerScalar::wait_for_condition(self.index(), timeout).into(),
         );
         let awoken = result.expect("Ticktimer: failure to send WaitForCondition command")[0] == 0;
 
@@ -119,17 +119,11 @@ fn wait_ms(&self, mutex: &Mutex, ms: usize) -> bool {
     }
 
     pub unsafe fn wait(&self, mutex: &Mutex) {
-        // Wait for 0 ms, which is a special case to "wait forever"
-        self.wait_ms(mutex, 0);
+        self.wait_for(mutex, None);
     }
 
     pub unsafe fn wait_timeout(&self, mutex: &Mutex, dur: Duration) -> bool {
-        let mut millis = dur.as_millis() as usize;
-        // Ensure we don't wait for 0 ms, which would cause us to wait forever
-        if millis == 0 {
-            millis = 1;
-        }
-        self.wait_ms(mutex, millis)
+        self.wait_for(mutex, Some(dur))
     }
 }
 
@@ -56,15 +56,11 @@ pub unsafe fn park_timeout(self: Pin<&Self>, timeout: Duration) {
         // The state has gone from EMPTY (0) to PARKED (-1)
         assert!(state == EMPTY);
 
-        // A value of zero indicates an indefinite wait. Clamp the number of
-        // milliseconds to the allowed range.
-        let millis = usize::max(timeout.as_millis().try_into().unwrap_or(usize::MAX), 1);
-
         // The state is now PARKED (-1). Wait until the `unpark` wakes us up,
         // or things time out.
         let _was_timeout = blocking_scalar(
             ticktimer_server(),
-            TicktimerScalar::WaitForCondition(self.index(), millis).into(),
+            TicktimerScalar::wait_for_condition(self.index(), Some(timeout)).into(),
         )
         .expect("failed to send WaitForCondition command")[0]
             != 0;
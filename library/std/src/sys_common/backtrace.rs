@@ -132,6 +132,22 @@ unsafe fn _print_fmt(fmt: &mut fmt::Formatter<'_>, print_fmt: PrintFmt) -> fmt::
         idx += 1;
         res.is_ok()
     });
+    // `backtrace` can't walk the stack on Xous, so print the bare addresses found
+    // by the unwinder instead. They're also sent to the log server with the panic.
+    #[cfg(target_os = "xous")]
+    crate::sys::backtrace::trace(|frame| {
+        if print_fmt == PrintFmt::Short && idx > MAX_NB_FRAMES {
+            return false;
+        }
+        res = bt_fmt.frame().print_raw(
+            crate::ptr::without_provenance_mut(frame.ip),
+            None,
+            None,
+            None,
+        );
+        idx += 1;
+        res.is_ok()
+    });
     res?;
     bt_fmt.finish()?;
     if print_fmt == PrintFmt::Short {
@@ -103,3 +103,21 @@ pub unsafe fn _Unwind_DeleteException(exception: *mut _Unwind_Exception) {
     let exception = unsafe { &mut *(exception as *mut UnwindException) };
     unsafe { unwinding::abi::_Unwind_DeleteException(exception) }
 }
+
+pub type _Unwind_Trace_Fn =
+    extern "C" fn(ctx: *mut _Unwind_Context, arg: *mut c_void) -> _Unwind_Reason_Code;
+
+pub unsafe fn _Unwind_GetIP(ctx: *mut _Unwind_Context) -> _Unwind_Word {
+    let ctx = unsafe { &mut *(ctx as *mut UnwindContext<'_>) };
+    unwinding::abi::_Unwind_GetIP(ctx) as _Unwind_Word
+}
+
+pub unsafe fn _Unwind_Backtrace(
+    trace: _Unwind_Trace_Fn,
+    trace_argument: *mut c_void,
+) -> _Unwind_Reason_Code {
+    // `_Unwind_Context` and `_Unwind_Reason_Code` have the same representation as the
+    // types `unwinding` uses, so the callback can be handed over unchanged.
+    let trace: unwinding::abi::UnwindTraceFn = unsafe { core::mem::transmute(trace) };
+    unsafe { core::mem::transmute(unwinding::abi::_Unwind_Backtrace(trace, trace_argument)) }
+}